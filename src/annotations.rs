@@ -0,0 +1,243 @@
+// Schema declarations written as comments directly above a key.
+//
+//     # Port the server listens on
+//     # @type int
+//     # @required
+//     PORT=8080
+//
+// A comment whose text starts with `@` is an annotation; it applies to the key that ends
+// the run of comments it belongs to. Supported annotations:
+// - `@type string|int|bool|url|enum|duration`
+// - `@enum a|b|c`, which also sets the type to enum
+// - `@pattern REGEX` and `@default VALUE`
+// - `@required`, `@secret` and `@public`, which may be followed by a description
+//
+// Annotations mean the same as the matching fields of a schema file, and the resulting
+// specs are used by `validate`, `export --mask-secrets` and `example`.
+
+use crate::schema::compile_pattern;
+use crate::{line_numbers, Diagnostic, EnvLine, KeySpec, Schema, ValueType};
+use std::collections::HashMap;
+
+/// Collect the specs declared by annotations in `lines`, together with errors for
+/// malformed annotations and warnings for unknown ones or ones not attached to a key
+pub fn annotated_schema(lines: &[EnvLine]) -> (Schema, Vec<Diagnostic>) {
+    let numbers = line_numbers(lines);
+    let mut schema = Schema::default();
+    let mut diagnostics = Vec::new();
+    let mut annotated_lines: HashMap<&str, usize> = HashMap::new();
+    let mut pending = Vec::new();
+
+    for (line, &line_no) in lines.iter().zip(&numbers) {
+        match line {
+            EnvLine::Comment(comment) => {
+                if let Some(annotation) = Annotation::parse(comment, line_no) {
+                    pending.push(annotation);
+                }
+            }
+            EnvLine::KeyValue { key, .. } if !pending.is_empty() => {
+                let spec = schema.keys.entry(key.clone()).or_default();
+                for annotation in pending.drain(..) {
+                    match annotation.apply(spec) {
+                        Ok(true) => {}
+                        Ok(false) => diagnostics.push(unknown(annotation)),
+                        Err(message) => diagnostics.push(annotation.error(message)),
+                    }
+                }
+                annotated_lines.insert(key, line_no);
+            }
+            EnvLine::KeyValue { .. } => {}
+            EnvLine::Empty(_) => diagnostics.extend(pending.drain(..).map(detached)),
+        }
+    }
+    diagnostics.extend(pending.into_iter().map(detached));
+
+    for (key, spec) in &schema.keys {
+        if let Some(problem) = spec.inconsistency(key) {
+            diagnostics.push(Diagnostic::error(annotated_lines[key.as_str()], 1, problem));
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    (schema, diagnostics)
+}
+
+fn detached(annotation: Annotation) -> Diagnostic {
+    Diagnostic::warning(
+        annotation.line,
+        annotation.column,
+        format!(
+            "annotation '@{}' is not directly above a key",
+            annotation.name
+        ),
+    )
+}
+
+/// Comments such as `# @todo rotate` may look like annotations without being meant as
+/// one, so an unknown name is only worth a warning
+fn unknown(annotation: Annotation) -> Diagnostic {
+    Diagnostic::warning(
+        annotation.line,
+        annotation.column,
+        format!("unknown annotation '@{}' is ignored", annotation.name),
+    )
+}
+
+struct Annotation<'a> {
+    name: &'a str,
+    argument: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Annotation<'a> {
+    fn parse(comment: &'a str, line: usize) -> Option<Self> {
+        let text = comment.trim_start().strip_prefix('#')?.trim_start();
+        let body = text.strip_prefix('@')?;
+        let (name, argument) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+        Some(Self {
+            name,
+            argument: argument.trim(),
+            line,
+            column: comment.len() - text.len() + 1,
+        })
+    }
+
+    /// Apply the annotation to `spec`; returns false when the name is not a known annotation
+    fn apply(&self, spec: &mut KeySpec) -> Result<bool, String> {
+        let argument = || {
+            if self.argument.is_empty() {
+                Err(format!("'@{}' needs a value", self.name))
+            } else {
+                Ok(self.argument)
+            }
+        };
+
+        match self.name {
+            "type" => spec.value_type = argument()?.parse()?,
+            "enum" => {
+                spec.values = argument()?
+                    .split('|')
+                    .map(|v| v.trim().to_string())
+                    .collect();
+                spec.value_type = ValueType::Enum;
+            }
+            "pattern" => spec.pattern = Some(compile_pattern(argument()?)?),
+            "default" => spec.default = Some(argument()?.to_string()),
+            "required" => spec.required = true,
+            "secret" => spec.secret = true,
+            "public" => spec.public = true,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn error(&self, message: String) -> Diagnostic {
+        Diagnostic::error(self.line, self.column, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_env_file, Severity};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_annotations_attach_to_following_key() {
+        let content = "\
+# Port the server listens on
+# @type int
+#   @required
+PORT=8080
+# @enum debug | info|warn
+# @default info
+LOG_LEVEL=info
+# @secret never commit this
+# @pattern [a-f0-9]+
+API_KEY=abc
+# @public
+HOST=localhost
+PLAIN=1
+";
+        let (schema, diagnostics) = annotated_schema(&parse_env_file(content));
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(
+            schema.keys.keys().collect::<Vec<_>>(),
+            vec!["API_KEY", "HOST", "LOG_LEVEL", "PORT"]
+        );
+
+        let port = &schema.keys["PORT"];
+        assert_eq!(port.value_type, ValueType::Int);
+        assert!(port.required && !port.secret);
+
+        let level = &schema.keys["LOG_LEVEL"];
+        assert_eq!(level.value_type, ValueType::Enum);
+        assert_eq!(level.values, vec!["debug", "info", "warn"]);
+        assert_eq!(level.default.as_deref(), Some("info"));
+
+        let api_key = &schema.keys["API_KEY"];
+        assert!(api_key.secret);
+        assert!(api_key.pattern.as_ref().unwrap().is_match("abc"));
+        assert!(schema.keys["HOST"].public);
+    }
+
+    #[test]
+    fn test_annotation_problems() {
+        let content = "\
+# @type float
+A=1
+# @requird
+B=2
+# @enum
+C=3
+# @secret
+# @public
+D=4
+# @type int
+# @default abc
+E=5
+# @required
+
+F=6
+# @secret
+";
+        let (_, diagnostics) = annotated_schema(&parse_env_file(content));
+        let rendered: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "1:3: error: unknown type 'float' (expected string, int, bool, url, enum or duration)",
+                "3:3: warning: unknown annotation '@requird' is ignored",
+                "5:3: error: '@enum' needs a value",
+                "9:1: error: 'D' cannot be both secret and public",
+                "12:1: error: invalid default: 'E' must be an integer (got \"abc\")",
+                "13:3: warning: annotation '@required' is not directly above a key",
+                "16:3: warning: annotation '@secret' is not directly above a key",
+            ]
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics[5].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_unknown_annotations_do_not_fail_validation() {
+        let content = "# @todo rotate this\n# @deprecated\n# @type int\nPORT=80\n";
+        let (schema, diagnostics) = annotated_schema(&parse_env_file(content));
+        assert_eq!(schema.keys["PORT"].value_type, ValueType::Int);
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity == Severity::Warning));
+        assert_eq!(diagnostics.len(), 2);
+    }
+
+    #[test]
+    fn test_ordinary_comments_are_not_annotations() {
+        let content = "# contact @ops for access\n# email: a@b.c\nKEY=1\n";
+        let (schema, diagnostics) = annotated_schema(&parse_env_file(content));
+        assert!(schema.keys.is_empty());
+        assert!(diagnostics.is_empty());
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.
@@ -0,0 +1,415 @@
+// Encrypting individual values so that files with secrets can be committed.
+//
+// An encrypted value is written as `enc:v1:` followed by the base64 of a random 24-byte
+// nonce and the XChaCha20-Poly1305 ciphertext with its tag. The key name is bound in as
+// associated data, so a value moved to a different key no longer decrypts. Other keys
+// stay in plaintext and remain readable in diffs.
+//
+// The 32-byte key is base64 text, found in this order:
+// 1. The file given with `--key-file`
+// 2. The `DOTENV_KEY` environment variable
+// 3. A `<file>.key` sidecar next to the env file, e.g. `.env.production.key`
+
+use crate::{find_definition, DuplicatePolicy, EnvLine};
+use anyhow::{Context, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::Rng;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Marks a value as encrypted, naming the envelope version
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Environment variable holding the base64 key, for CI and deployments
+pub const KEY_ENV_VAR: &str = "DOTENV_KEY";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+
+/// Whether `value` is an encrypted envelope
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// The sidecar key file used for `env_file` when no other key is given
+pub fn key_file_path(env_file: &Path) -> PathBuf {
+    let mut path = env_file.as_os_str().to_owned();
+    path.push(".key");
+    PathBuf::from(path)
+}
+
+/// A symmetric key for encrypting and decrypting values
+pub struct EncryptionKey {
+    cipher: XChaCha20Poly1305,
+    encoded: String,
+}
+
+impl EncryptionKey {
+    /// A new random key
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; KEY_LEN];
+        rand::rng().fill(&mut bytes);
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parse a key written as base64, ignoring surrounding whitespace
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = BASE64_STANDARD
+            .decode(encoded.trim())
+            .context("Encryption key is not valid base64")?;
+        if bytes.len() != KEY_LEN {
+            anyhow::bail!(
+                "Encryption key must be {KEY_LEN} bytes, found {}",
+                bytes.len()
+            );
+        }
+        Ok(Self::from_bytes(&bytes))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(bytes.into()),
+            encoded: BASE64_STANDARD.encode(bytes),
+        }
+    }
+
+    /// The key as base64, as stored in key files and `DOTENV_KEY`
+    pub fn to_base64(&self) -> &str {
+        &self.encoded
+    }
+
+    /// Find the key for `env_file`: `key_file` if given, then `DOTENV_KEY`, then the
+    /// sidecar key file. Returns `None` when none of them exist.
+    pub fn find(env_file: &Path, key_file: Option<&Path>) -> Result<Option<Self>> {
+        if let Some(key_file) = key_file {
+            return Self::read(key_file).map(Some);
+        }
+        if let Ok(encoded) = env::var(KEY_ENV_VAR) {
+            return Self::from_base64(&encoded)
+                .with_context(|| format!("Invalid {KEY_ENV_VAR}"))
+                .map(Some);
+        }
+        let sidecar = key_file_path(env_file);
+        if sidecar.exists() {
+            return Self::read(&sidecar).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read key file: {}", path.display()))?;
+        Self::from_base64(&content).with_context(|| format!("Invalid key file: {}", path.display()))
+    }
+
+    /// Write the key to a new file readable only by its owner
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("Failed to create key file: {}", path.display()))?;
+        writeln!(file, "{}", self.encoded)
+            .with_context(|| format!("Failed to write key file: {}", path.display()))
+    }
+
+    /// Encrypt the value of `key` into an `enc:v1:` envelope
+    pub fn encrypt(&self, key: &str, value: &str) -> String {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rng().fill(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: key.as_bytes(),
+                },
+            )
+            .expect("encrypting into a Vec cannot fail");
+
+        let mut envelope = nonce.to_vec();
+        envelope.extend(ciphertext);
+        format!("{ENCRYPTED_PREFIX}{}", BASE64_STANDARD.encode(envelope))
+    }
+
+    /// Decrypt an `enc:v1:` envelope stored under `key`
+    pub fn decrypt(&self, key: &str, value: &str) -> Result<String> {
+        let encoded = value
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .with_context(|| format!("{key} is not encrypted"))?;
+        let envelope = BASE64_STANDARD
+            .decode(encoded)
+            .with_context(|| format!("Encrypted value of {key} is not valid base64"))?;
+        if envelope.len() < NONCE_LEN {
+            anyhow::bail!("Encrypted value of {key} is truncated");
+        }
+
+        let (nonce, ciphertext) = envelope.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: key.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                anyhow::anyhow!("Failed to decrypt {key}: wrong key or corrupted value")
+            })?;
+        String::from_utf8(plaintext)
+            .with_context(|| format!("Decrypted value of {key} is not valid UTF-8"))
+    }
+}
+
+/// Encrypt the definitions `policy` selects for `keys`, skipping values that are already
+/// encrypted. Returns the keys that were encrypted.
+pub fn encrypt_env_vars(
+    lines: &mut [EnvLine],
+    keys: &[String],
+    encryption_key: &EncryptionKey,
+    policy: DuplicatePolicy,
+) -> Result<Vec<String>> {
+    transform_values(lines, keys, policy, |key, value| {
+        Ok((!is_encrypted(value)).then(|| encryption_key.encrypt(key, value)))
+    })
+}
+
+/// Decrypt the definitions `policy` selects for `keys`, skipping values that are not
+/// encrypted. Returns the keys that were decrypted.
+pub fn decrypt_env_vars(
+    lines: &mut [EnvLine],
+    keys: &[String],
+    encryption_key: &EncryptionKey,
+    policy: DuplicatePolicy,
+) -> Result<Vec<String>> {
+    transform_values(lines, keys, policy, |key, value| {
+        is_encrypted(value)
+            .then(|| encryption_key.decrypt(key, value))
+            .transpose()
+    })
+}
+
+/// Encrypt the value of every `KEY=value` pair whose key currently holds an encrypted
+/// value, so that `set` and `randomize` never put a secret back in plaintext. Fails when
+/// such a key needs `encryption_key` and none is available.
+pub fn encrypt_replacement_pairs(
+    lines: &[EnvLine],
+    pairs: Vec<String>,
+    encryption_key: Option<&EncryptionKey>,
+    policy: DuplicatePolicy,
+) -> Result<Vec<String>> {
+    let mut encrypted_pairs = Vec::with_capacity(pairs.len());
+    for pair in pairs {
+        // Malformed pairs are passed through for set_env_vars to report
+        let Some((key, value)) = pair.split_once('=') else {
+            encrypted_pairs.push(pair);
+            continue;
+        };
+        let key = key.trim();
+        let is_secret = find_definition(lines, key, policy)?.is_some_and(
+            |idx| matches!(&lines[idx], EnvLine::KeyValue { value, .. } if is_encrypted(value)),
+        );
+
+        if is_secret && !is_encrypted(value) {
+            let encryption_key = encryption_key.with_context(|| {
+                format!("{key} is encrypted and no key was found to encrypt its new value")
+            })?;
+            encrypted_pairs.push(format!("{key}={}", encryption_key.encrypt(key, value)));
+        } else {
+            encrypted_pairs.push(pair);
+        }
+    }
+    Ok(encrypted_pairs)
+}
+
+/// Decrypt every encrypted value in place, so that reading commands see plaintext
+pub fn decrypt_all_values(lines: &mut [EnvLine], encryption_key: &EncryptionKey) -> Result<()> {
+    for line in lines {
+        if let EnvLine::KeyValue { key, value, .. } = line {
+            if is_encrypted(value) {
+                *value = encryption_key.decrypt(key, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn transform_values(
+    lines: &mut [EnvLine],
+    keys: &[String],
+    policy: DuplicatePolicy,
+    transform: impl Fn(&str, &str) -> Result<Option<String>>,
+) -> Result<Vec<String>> {
+    let mut changed = Vec::new();
+    for key in keys {
+        let idx = find_definition(lines, key, policy)?
+            .with_context(|| format!("Key not found: {key}"))?;
+        if let EnvLine::KeyValue { value, .. } = &mut lines[idx] {
+            if let Some(new_value) = transform(key, value)? {
+                *value = new_value;
+                changed.push(key.clone());
+            }
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_env_file, write_env_file};
+    use pretty_assertions::assert_eq;
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = EncryptionKey::generate();
+        for value in ["", "hunter2", "multi\nline 'quoted' $value", "ünïcødé"] {
+            let envelope = key.encrypt("SECRET", value);
+            assert!(is_encrypted(&envelope));
+            assert!(!envelope.contains(value) || value.is_empty());
+            assert_eq!(key.decrypt("SECRET", &envelope).unwrap(), value);
+        }
+        assert_ne!(key.encrypt("A", "x"), key.encrypt("A", "x"));
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_key_name_and_tampering() {
+        let key = EncryptionKey::generate();
+        let envelope = key.encrypt("A", "secret");
+
+        assert!(key.decrypt("B", &envelope).is_err());
+        assert!(EncryptionKey::generate().decrypt("A", &envelope).is_err());
+
+        let mut bytes = BASE64_STANDARD
+            .decode(envelope.strip_prefix(ENCRYPTED_PREFIX).unwrap())
+            .unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        let tampered = format!("{ENCRYPTED_PREFIX}{}", BASE64_STANDARD.encode(bytes));
+        assert!(key.decrypt("A", &tampered).is_err());
+
+        assert!(key.decrypt("A", "enc:v1:AAAA").is_err());
+        assert!(key.decrypt("A", "enc:v1:not base64!").is_err());
+        assert!(key.decrypt("A", "plain").is_err());
+    }
+
+    #[test]
+    fn test_key_from_base64() {
+        let key = EncryptionKey::generate();
+        let parsed = EncryptionKey::from_base64(&format!("  {}\n", key.to_base64())).unwrap();
+        assert_eq!(parsed.decrypt("A", &key.encrypt("A", "v")).unwrap(), "v");
+
+        assert!(EncryptionKey::from_base64("not base64!").is_err());
+        assert!(EncryptionKey::from_base64(&BASE64_STANDARD.encode([0u8; 16])).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt_env_vars() {
+        let key = EncryptionKey::generate();
+        let mut lines = parse_env_file("# db\nHOST=localhost\nPASSWORD='hunter 2' # rotate\n");
+
+        let encrypted = encrypt_env_vars(
+            &mut lines,
+            &keys(&["PASSWORD"]),
+            &key,
+            DuplicatePolicy::LastWins,
+        )
+        .unwrap();
+        assert_eq!(encrypted, vec!["PASSWORD"]);
+        let written = write_env_file(&lines);
+        assert!(written.starts_with("# db\nHOST=localhost\nPASSWORD='enc:v1:"));
+        assert!(written.ends_with("' # rotate\n"));
+        assert!(!written.contains("hunter"));
+
+        // Already encrypted values are left alone, so re-running does not churn the file
+        let again = encrypt_env_vars(
+            &mut lines,
+            &keys(&["PASSWORD"]),
+            &key,
+            DuplicatePolicy::LastWins,
+        )
+        .unwrap();
+        assert!(again.is_empty());
+        assert_eq!(write_env_file(&lines), written);
+
+        let mut reread = parse_env_file(&written);
+        decrypt_env_vars(
+            &mut reread,
+            &keys(&["PASSWORD", "HOST"]),
+            &key,
+            DuplicatePolicy::LastWins,
+        )
+        .unwrap();
+        assert_eq!(
+            write_env_file(&reread),
+            "# db\nHOST=localhost\nPASSWORD='hunter 2' # rotate\n"
+        );
+
+        assert!(encrypt_env_vars(
+            &mut lines,
+            &keys(&["MISSING"]),
+            &key,
+            DuplicatePolicy::LastWins
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_decrypt_all_values() {
+        let key = EncryptionKey::generate();
+        let mut lines = parse_env_file(&format!("A=plain\nB={}\n", key.encrypt("B", "secret")));
+        decrypt_all_values(&mut lines, &key).unwrap();
+        assert_eq!(write_env_file(&lines), "A=plain\nB=secret\n");
+
+        let mut lines = parse_env_file(&format!("B={}\n", key.encrypt("B", "secret")));
+        assert!(decrypt_all_values(&mut lines, &EncryptionKey::generate()).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_replacement_pairs() {
+        let key = EncryptionKey::generate();
+        let lines = parse_env_file(&format!("HOST=a\nPIN={}\n", key.encrypt("PIN", "1234")));
+        let pairs = keys(&["HOST=b", "PIN=5678", "NEW=c"]);
+
+        let encrypted =
+            encrypt_replacement_pairs(&lines, pairs.clone(), Some(&key), DuplicatePolicy::LastWins)
+                .unwrap();
+        assert_eq!(encrypted[0], "HOST=b");
+        assert_eq!(encrypted[2], "NEW=c");
+        let pin = encrypted[1].strip_prefix("PIN=").unwrap();
+        assert_eq!(key.decrypt("PIN", pin).unwrap(), "5678");
+
+        let err =
+            encrypt_replacement_pairs(&lines, pairs, None, DuplicatePolicy::LastWins).unwrap_err();
+        assert!(err.to_string().contains("PIN is encrypted"));
+        assert!(encrypt_replacement_pairs(
+            &lines,
+            keys(&["HOST=b"]),
+            None,
+            DuplicatePolicy::LastWins
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_key_file_path() {
+        assert_eq!(
+            key_file_path(Path::new("config/.env.production")),
+            PathBuf::from("config/.env.production.key")
+        );
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.
@@ -0,0 +1,175 @@
+// Comparing the variables of two env files.
+//
+// `diff_env_vars` works on the ordered key/value views of both files, so only effective
+// values are compared: formatting, comments and overridden duplicates do not count as
+// differences. Results list keys in the order of the first file, followed by keys that
+// only the second file defines.
+
+use crate::quote_value;
+use std::collections::{HashMap, HashSet};
+
+/// One key that differs between two sets of variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvDiff {
+    /// Only the second file defines the key
+    Added { key: String, value: String },
+    /// Only the first file defines the key
+    Removed { key: String, value: String },
+    /// Both files define the key with different values
+    Changed {
+        key: String,
+        old: String,
+        new: String,
+    },
+}
+
+/// Placeholder shown instead of values unless they are explicitly revealed
+pub const MASKED_VALUE: &str = "********";
+
+impl EnvDiff {
+    pub fn key(&self) -> &str {
+        match self {
+            EnvDiff::Added { key, .. } | EnvDiff::Removed { key, .. } => key,
+            EnvDiff::Changed { key, .. } => key,
+        }
+    }
+
+    /// Render as a single line: `+ KEY=value`, `- KEY=value` or `~ KEY: old -> new`.
+    /// Values are masked unless `show_values` is set, and left out entirely for
+    /// `keys_only`.
+    pub fn render(&self, show_values: bool, keys_only: bool) -> String {
+        let marker = match self {
+            EnvDiff::Added { .. } => '+',
+            EnvDiff::Removed { .. } => '-',
+            EnvDiff::Changed { .. } => '~',
+        };
+        if keys_only {
+            return format!("{marker} {}", self.key());
+        }
+
+        let display = |value: &str| {
+            if show_values {
+                quote_value(value)
+            } else {
+                MASKED_VALUE.to_string()
+            }
+        };
+        match self {
+            EnvDiff::Added { key, value } | EnvDiff::Removed { key, value } => {
+                format!("{marker} {key}={}", display(value))
+            }
+            EnvDiff::Changed { key, old, new } => {
+                format!("{marker} {key}: {} -> {}", display(old), display(new))
+            }
+        }
+    }
+}
+
+/// Compare two ordered sets of variables
+pub fn diff_env_vars(old: &[(String, String)], new: &[(String, String)]) -> Vec<EnvDiff> {
+    let new_values: HashMap<&str, &str> = new
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+    let old_keys: HashSet<&str> = old.iter().map(|(key, _)| key.as_str()).collect();
+
+    let mut diffs = Vec::new();
+    for (key, value) in old {
+        match new_values.get(key.as_str()) {
+            None => diffs.push(EnvDiff::Removed {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            Some(new_value) if new_value != value => diffs.push(EnvDiff::Changed {
+                key: key.clone(),
+                old: value.clone(),
+                new: new_value.to_string(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, value) in new {
+        if !old_keys.contains(key.as_str()) {
+            diffs.push(EnvDiff::Added {
+                key: key.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_env_vars() {
+        let diffs = diff_env_vars(
+            &vars(&[("SAME", "1"), ("GONE", "x"), ("CHANGED", "old")]),
+            &vars(&[("NEW", "y"), ("CHANGED", "new"), ("SAME", "1")]),
+        );
+        assert_eq!(
+            diffs,
+            vec![
+                EnvDiff::Removed {
+                    key: "GONE".to_string(),
+                    value: "x".to_string()
+                },
+                EnvDiff::Changed {
+                    key: "CHANGED".to_string(),
+                    old: "old".to_string(),
+                    new: "new".to_string()
+                },
+                EnvDiff::Added {
+                    key: "NEW".to_string(),
+                    value: "y".to_string()
+                },
+            ]
+        );
+        assert!(diff_env_vars(&vars(&[("A", "1")]), &vars(&[("A", "1")])).is_empty());
+    }
+
+    #[test]
+    fn test_render_masks_by_default() {
+        let changed = EnvDiff::Changed {
+            key: "PASSWORD".to_string(),
+            old: "hunter2".to_string(),
+            new: "two words".to_string(),
+        };
+        assert_eq!(
+            changed.render(false, false),
+            "~ PASSWORD: ******** -> ********"
+        );
+        assert_eq!(
+            changed.render(true, false),
+            "~ PASSWORD: hunter2 -> \"two words\""
+        );
+        assert_eq!(changed.render(true, true), "~ PASSWORD");
+
+        let added = EnvDiff::Added {
+            key: "A".to_string(),
+            value: "line1\nline2".to_string(),
+        };
+        assert_eq!(added.render(false, false), "+ A=********");
+        assert_eq!(added.render(true, false), "+ A=\"line1\\nline2\"");
+
+        let removed = EnvDiff::Removed {
+            key: "B".to_string(),
+            value: "".to_string(),
+        };
+        assert_eq!(removed.render(true, false), "- B=");
+        assert_eq!(removed.render(false, true), "- B");
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.
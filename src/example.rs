@@ -0,0 +1,189 @@
+// Generating a shareable example file, such as `.env.example`, from a real env file.
+//
+// Every line is kept in place: comments, blank lines and assignments with their
+// `export` prefix, quoting and inline comments. Only values change, guided by the
+// annotations above each key:
+// - `@public` keys keep their value
+// - `@default` keys get their default
+// - everything else gets a placeholder, which for `PlaceholderStyle::Type` is the
+//   annotated type or one guessed from the value
+
+use crate::{annotated_schema, EnvLine, KeySpec, ValueType};
+use clap::ValueEnum;
+
+/// What replaces the values of keys that are not marked public
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PlaceholderStyle {
+    /// An empty value
+    #[default]
+    Blank,
+    /// The kind of value expected, such as `<int>` or `<url>`, taken from `@type` or
+    /// `@enum` or guessed from the real value, and left blank when nothing more
+    /// specific than a string fits
+    Type,
+}
+
+/// Copy `lines` with every value replaced by a placeholder, except for keys annotated
+/// as public
+pub fn example_env_lines(lines: &[EnvLine], style: PlaceholderStyle) -> Vec<EnvLine> {
+    let (schema, _) = annotated_schema(lines);
+    let no_spec = KeySpec::default();
+
+    lines
+        .iter()
+        .map(|line| match line {
+            EnvLine::KeyValue { key, value, format } => {
+                let spec = schema.keys.get(key).unwrap_or(&no_spec);
+                EnvLine::KeyValue {
+                    key: key.clone(),
+                    value: example_value(value, spec, style),
+                    format: format.clone(),
+                }
+            }
+            _ => line.clone(),
+        })
+        .collect()
+}
+
+fn example_value(value: &str, spec: &KeySpec, style: PlaceholderStyle) -> String {
+    if spec.public {
+        return value.to_string();
+    }
+    if let Some(default) = &spec.default {
+        return default.clone();
+    }
+
+    match (style, spec.value_type) {
+        (PlaceholderStyle::Blank, _) => String::new(),
+        (PlaceholderStyle::Type, ValueType::Enum) => format!("<{}>", spec.values.join("|")),
+        (PlaceholderStyle::Type, ValueType::String) => type_hint(value)
+            .map(|hint| format!("<{hint}>"))
+            .unwrap_or_default(),
+        (PlaceholderStyle::Type, value_type) => format!("<{}>", value_type.name()),
+    }
+}
+
+/// The most specific of bool, int, duration and url that describes `value`
+fn type_hint(value: &str) -> Option<&'static str> {
+    if value.is_empty() {
+        None
+    } else if matches!(value, "true" | "false") {
+        Some("bool")
+    } else if value.parse::<i64>().is_ok() {
+        Some("int")
+    } else if is_duration(value) {
+        Some("duration")
+    } else if value.contains("://") {
+        Some("url")
+    } else {
+        None
+    }
+}
+
+/// A number followed by a unit such as `500ms`, `30s` or `1h30m`
+pub(crate) fn is_duration(value: &str) -> bool {
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return false;
+        }
+        rest = &rest[digits..];
+        let Some(unit) = ["ms", "s", "m", "h", "d"]
+            .into_iter()
+            .find(|unit| rest.starts_with(unit))
+        else {
+            return false;
+        };
+        rest = &rest[unit.len()..];
+    }
+    !value.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_env_file, write_env_file};
+    use pretty_assertions::assert_eq;
+
+    fn example(content: &str, style: PlaceholderStyle) -> String {
+        write_env_file(&example_env_lines(&parse_env_file(content), style))
+    }
+
+    #[test]
+    fn test_example_blanks_values_and_keeps_structure() {
+        let content = "\
+# Database
+export DB_HOST=db.internal
+DB_PASSWORD='s3cret' # rotate monthly
+
+API_KEY=\"abc\\ndef\"
+";
+        assert_eq!(
+            example(content, PlaceholderStyle::Blank),
+            "# Database\nexport DB_HOST=\nDB_PASSWORD='' # rotate monthly\n\nAPI_KEY=\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_example_keeps_public_values() {
+        let content = "\
+# Port the server listens on
+# @public
+PORT=8080
+TOKEN=abc
+#   @public   shown in the docs
+LOG_LEVEL=info
+
+# @public
+
+SECRET=hidden
+";
+        assert_eq!(
+            example(content, PlaceholderStyle::Blank),
+            "# Port the server listens on\n# @public\nPORT=8080\nTOKEN=\n#   @public   shown in the docs\nLOG_LEVEL=info\n\n# @public\n\nSECRET=\n"
+        );
+    }
+
+    #[test]
+    fn test_example_uses_annotations() {
+        let content = "\
+# @type int
+WORKERS=4abc
+# @enum debug|info
+LOG_LEVEL=debug
+# @default 30s
+TIMEOUT=5s
+# @secret
+# @default changeme
+PASSWORD=hunter2
+";
+        assert_eq!(
+            example(content, PlaceholderStyle::Type),
+            "# @type int\nWORKERS=\"<int>\"\n# @enum debug|info\nLOG_LEVEL=\"<debug|info>\"\n# @default 30s\nTIMEOUT=30s\n# @secret\n# @default changeme\nPASSWORD=changeme\n"
+        );
+        assert!(example(content, PlaceholderStyle::Blank).contains("\nWORKERS=\n"));
+    }
+
+    #[test]
+    fn test_example_type_hints() {
+        let content =
+            "DEBUG=true\nPORT=8080\nTIMEOUT=1m30s\nURL=postgres://u:p@h/db\nNAME=app\nEMPTY=\n";
+        assert_eq!(
+            example(content, PlaceholderStyle::Type),
+            "DEBUG=\"<bool>\"\nPORT=\"<int>\"\nTIMEOUT=\"<duration>\"\nURL=\"<url>\"\nNAME=\nEMPTY=\n"
+        );
+    }
+
+    #[test]
+    fn test_is_duration() {
+        for value in ["500ms", "30s", "5m", "1h30m", "7d"] {
+            assert!(is_duration(value), "{value}");
+        }
+        for value in ["", "30", "s", "1.5s", "10 s", "5min"] {
+            assert!(!is_duration(value), "{value}");
+        }
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.
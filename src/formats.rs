@@ -0,0 +1,1056 @@
+// Export formatters for tools that consume environment variables in their own formats.
+//
+// Each formatter takes the ordered key/value view produced by `get_ordered_env_vars` or
+// `order_env_vars` and returns the complete output, so the same file always renders to
+// the same bytes. Formatters fail instead of emitting output the target tool would
+// misread.
+//
+// Supported targets:
+// - Docker `--env-file`: `KEY=value` taken literally, with no quoting or escaping
+// - Docker Compose: an `environment:` mapping with `$` escaped against interpolation
+// - Kubernetes: ConfigMap and Secret manifests, with glob patterns routing keys to the Secret
+// - systemd: an `EnvironmentFile=` file, or a unit drop-in with `Environment=` lines
+// - YAML and TOML: flat mappings whose values always read back as strings
+// - Shells other than bash: POSIX sh (also used for zsh), fish, PowerShell and nushell,
+//   each with an escaper that leaves nothing for the shell to expand or execute
+
+use crate::{is_plain_value_char, is_valid_key, OrderedEnvVars};
+use anyhow::Result;
+use base64::Engine;
+
+/// Render `docker run --env-file` lines. Docker reads everything after the first `=`
+/// literally, so values are written as-is; values containing line breaks cannot be
+/// represented and are rejected.
+pub fn format_docker(env_vars: &[(String, String)]) -> Result<String> {
+    let unrepresentable: Vec<&str> = env_vars
+        .iter()
+        .filter(|(_, value)| value.contains(['\n', '\r']))
+        .map(|(key, _)| key.as_str())
+        .collect();
+    if !unrepresentable.is_empty() {
+        anyhow::bail!(
+            "Docker env files cannot contain values with line breaks: {}",
+            unrepresentable.join(", ")
+        );
+    }
+
+    Ok(env_vars
+        .iter()
+        .map(|(key, value)| format!("{key}={value}\n"))
+        .collect())
+}
+
+/// Render an `environment:` mapping to paste under a docker-compose service. Values
+/// are always quoted so Compose keeps them as strings, and `$` is doubled so Compose
+/// does not interpolate it.
+pub fn format_compose(env_vars: &[(String, String)]) -> String {
+    let mut output = String::from("environment:\n");
+    for (key, value) in env_vars {
+        output.push_str(&format!(
+            "  {}: {}\n",
+            yaml_scalar(key),
+            yaml_quote(&value.replace('$', "$$"))
+        ));
+    }
+    output
+}
+
+/// Render a flat YAML mapping, quoting only values YAML would not read back as strings
+pub fn format_yaml(env_vars: &[(String, String)]) -> String {
+    if env_vars.is_empty() {
+        return "{}\n".to_string();
+    }
+
+    env_vars
+        .iter()
+        .map(|(key, value)| format!("{}: {}\n", yaml_scalar(key), yaml_scalar(value)))
+        .collect()
+}
+
+/// Render a flat TOML table with every value as a string
+pub fn format_toml(env_vars: &[(String, String)]) -> String {
+    env_vars
+        .iter()
+        .map(|(key, value)| format!("{} = {}\n", toml_key(key), toml_quote(value)))
+        .collect()
+}
+
+/// Write a key bare when TOML allows it, otherwise quoted so that dots and other
+/// punctuation do not turn it into a dotted key
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        toml_quote(key)
+    }
+}
+
+/// Keys that are not valid shell variable names. The shell formats leave them out, since
+/// a key such as `A$(touch pwned)` would otherwise run as code when the output is sourced.
+pub fn invalid_shell_keys(env_vars: &[(String, String)]) -> Vec<&str> {
+    env_vars
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .filter(|key| !is_valid_key(key))
+        .collect()
+}
+
+/// The variables whose keys can be written into shell code as-is
+fn shell_vars(env_vars: &[(String, String)]) -> impl Iterator<Item = &(String, String)> {
+    env_vars.iter().filter(|(key, _)| is_valid_key(key))
+}
+
+/// Render `export` statements for POSIX sh, dash and zsh, without bash-only syntax
+pub fn format_sh(env_vars: &[(String, String)]) -> String {
+    shell_vars(env_vars)
+        .map(|(key, value)| format!("export {key}={}\n", sh_escape(value)))
+        .collect()
+}
+
+/// Render `set -gx` commands for fish
+pub fn format_fish(env_vars: &[(String, String)]) -> String {
+    shell_vars(env_vars)
+        .map(|(key, value)| format!("set -gx {key} {}\n", fish_escape(value)))
+        .collect()
+}
+
+/// Render `$env:` assignments for PowerShell
+pub fn format_powershell(env_vars: &[(String, String)]) -> String {
+    shell_vars(env_vars)
+        .map(|(key, value)| format!("$env:{key} = {}\n", powershell_escape(value)))
+        .collect()
+}
+
+/// Render a nushell `load-env` call with a record of every variable
+pub fn format_nu(env_vars: &[(String, String)]) -> String {
+    if env_vars.is_empty() {
+        return "load-env {}\n".to_string();
+    }
+
+    let mut output = String::from("load-env {\n");
+    for (key, value) in env_vars {
+        output.push_str(&format!("    {}: {}\n", nu_key(key), nu_escape(value)));
+    }
+    output.push_str("}\n");
+    output
+}
+
+/// Write a record key bare when it is a plain identifier, otherwise as a quoted string
+fn nu_key(key: &str) -> String {
+    if is_valid_key(key) {
+        key.to_string()
+    } else {
+        nu_escape(key)
+    }
+}
+
+/// Escape a value for a POSIX shell. Anything beyond plain characters is single-quoted,
+/// where nothing is special except the closing quote, which is written as `'\''`.
+pub fn sh_escape(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(is_plain_value_char) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Escape a value for fish. Values are always single-quoted, where fish only treats
+/// `\'` and `\\` specially.
+pub fn fish_escape(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Escape a value for PowerShell. Values are always single-quoted, which PowerShell never
+/// expands; every kind of single quote it recognises, including typographic ones, is
+/// doubled.
+pub fn powershell_escape(value: &str) -> String {
+    let mut result = String::from('\'');
+    for ch in value.chars() {
+        if matches!(ch, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+            result.push(ch);
+        }
+        result.push(ch);
+    }
+    result.push('\'');
+    result
+}
+
+/// Escape a value for nushell. Plain double-quoted strings are never interpolated in nu,
+/// so only quotes, backslashes and control characters need escapes.
+pub fn nu_escape(value: &str) -> String {
+    let mut result = String::from('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Render a file for systemd's `EnvironmentFile=`
+pub fn format_systemd(env_vars: &[(String, String)]) -> String {
+    env_vars
+        .iter()
+        .map(|(key, value)| format!("{key}={}\n", systemd_escape(value)))
+        .collect()
+}
+
+/// Render a unit drop-in (e.g. `/etc/systemd/system/app.service.d/env.conf`) setting
+/// every variable with `Environment=`
+pub fn format_systemd_dropin(env_vars: &[(String, String)]) -> String {
+    let mut output = String::from("[Service]\n");
+    for (key, value) in env_vars {
+        output.push_str(&format!(
+            "Environment={}\n",
+            systemd_dropin_escape(&format!("{key}={value}"))
+        ));
+    }
+    output
+}
+
+/// Escape a value for an `EnvironmentFile=` line. systemd reads these with its own
+/// parser rather than a shell: inside double quotes only `"`, `\`, `` ` `` and `$` take a
+/// backslash, and newlines are kept literally.
+pub fn systemd_escape(value: &str) -> String {
+    if value.chars().all(is_plain_value_char) {
+        return value.to_string();
+    }
+
+    let mut result = String::from('"');
+    for ch in value.chars() {
+        if matches!(ch, '"' | '\\' | '`' | '$') {
+            result.push('\\');
+        }
+        result.push(ch);
+    }
+    result.push('"');
+    result
+}
+
+/// Quote a whole `KEY=value` assignment for an `Environment=` setting in a unit file.
+/// Unit files apply C-style escapes inside quotes and expand `%` specifiers, so both
+/// are escaped; `$` is not expanded there and is left alone.
+pub fn systemd_dropin_escape(assignment: &str) -> String {
+    let mut result = String::from('"');
+    for ch in assignment.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '%' => result.push_str("%%"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() && (c as u32) <= 0xff => {
+                result.push_str(&format!("\\x{:02x}", c as u32));
+            }
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Name and namespace of a generated Kubernetes object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestMetadata {
+    pub name: String,
+    pub namespace: Option<String>,
+}
+
+impl ManifestMetadata {
+    /// Check the name is a valid DNS subdomain and the namespace a valid DNS label, as
+    /// the API server requires
+    pub fn new(name: &str, namespace: Option<&str>) -> Result<Self> {
+        let is_dns_label = |s: &str| {
+            !s.is_empty()
+                && s.len() <= 63
+                && s.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                && !s.starts_with('-')
+                && !s.ends_with('-')
+        };
+
+        if name.len() > 253 || !name.split('.').all(is_dns_label) {
+            anyhow::bail!(
+                "Invalid Kubernetes name: {name} (use lowercase letters, digits, '-' and '.')"
+            );
+        }
+        if let Some(namespace) = namespace {
+            if !is_dns_label(namespace) {
+                anyhow::bail!(
+                    "Invalid Kubernetes namespace: {namespace} (use lowercase letters, digits and '-')"
+                );
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            namespace: namespace.map(str::to_string),
+        })
+    }
+
+    /// Derive a valid object name from an env file name, e.g. `.env.production` becomes
+    /// `env-production`
+    pub fn name_from_file(file_name: &str) -> String {
+        let mut name = String::new();
+        for c in file_name.to_lowercase().chars() {
+            if c.is_ascii_alphanumeric() {
+                name.push(c);
+            } else if !name.is_empty() && !name.ends_with('-') {
+                name.push('-');
+            }
+        }
+        name.truncate(63);
+        let name = name.trim_end_matches('-');
+
+        if name.is_empty() {
+            "env".to_string()
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+/// Render a ConfigMap holding every variable as plain text
+pub fn format_k8s_configmap(env_vars: &[(String, String)], metadata: &ManifestMetadata) -> String {
+    let data = env_vars
+        .iter()
+        .map(|(key, value)| (key.as_str(), yaml_quote(value)))
+        .collect();
+    k8s_manifest("ConfigMap", metadata, None, data)
+}
+
+/// Render an Opaque Secret holding every variable base64-encoded under `data:`
+pub fn format_k8s_secret(env_vars: &[(String, String)], metadata: &ManifestMetadata) -> String {
+    let data = env_vars
+        .iter()
+        .map(|(key, value)| {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(value);
+            if encoded.is_empty() {
+                (key.as_str(), yaml_quote(""))
+            } else {
+                (key.as_str(), encoded)
+            }
+        })
+        .collect();
+    k8s_manifest("Secret", metadata, Some("Opaque"), data)
+}
+
+fn k8s_manifest(
+    kind: &str,
+    metadata: &ManifestMetadata,
+    secret_type: Option<&str>,
+    data: Vec<(&str, String)>,
+) -> String {
+    let mut output = format!(
+        "apiVersion: v1\nkind: {kind}\nmetadata:\n  name: {}\n",
+        metadata.name
+    );
+    if let Some(namespace) = &metadata.namespace {
+        output.push_str(&format!("  namespace: {namespace}\n"));
+    }
+    if let Some(secret_type) = secret_type {
+        output.push_str(&format!("type: {secret_type}\n"));
+    }
+
+    if data.is_empty() {
+        output.push_str("data: {}\n");
+    } else {
+        output.push_str("data:\n");
+        for (key, value) in data {
+            output.push_str(&format!("  {}: {value}\n", yaml_scalar(key)));
+        }
+    }
+    output
+}
+
+/// Split variables into those matching none of `patterns` and those matching at least
+/// one, keeping their order
+pub fn partition_by_patterns(
+    env_vars: OrderedEnvVars,
+    patterns: &[String],
+) -> (OrderedEnvVars, OrderedEnvVars) {
+    let (matching, other) = env_vars
+        .into_iter()
+        .partition(|(key, _)| patterns.iter().any(|pattern| key_matches(pattern, key)));
+    (other, matching)
+}
+
+/// Match a key against a glob pattern where `*` matches any run of characters and `?`
+/// matches exactly one
+pub fn key_matches(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let key: Vec<char> = key.chars().collect();
+
+    let (mut p, mut k) = (0, 0);
+    // Position after the last `*` seen, and the key position it is currently absorbing up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while k < key.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, k));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == key[k] => {
+                p += 1;
+                k += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_k)) => {
+                    backtrack = Some((star_p, star_k + 1));
+                    p = star_p;
+                    k = star_k + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Quote a value as a YAML double-quoted scalar, which can represent any string
+pub fn yaml_quote(value: &str) -> String {
+    let mut result = String::from('"');
+
+    for ch in value.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            // Line and paragraph separators would be folded as line breaks, and a BOM is
+            // not allowed inside a document
+            c if c.is_control() || matches!(c, '\u{2028}' | '\u{2029}' | '\u{feff}') => {
+                let code = c as u32;
+                if code <= 0xff {
+                    result.push_str(&format!("\\x{code:02x}"));
+                } else {
+                    result.push_str(&format!("\\u{code:04x}"));
+                }
+            }
+            c => result.push(c),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+/// Write a key or value as a plain YAML scalar when it would unambiguously read back as
+/// the same string, and double-quoted otherwise. Anything that starts like a number,
+/// date or YAML indicator, or spells a YAML 1.1 boolean or null, is quoted.
+pub fn yaml_scalar(value: &str) -> String {
+    const RESERVED_WORDS: &[&str] = &[
+        "y", "yes", "n", "no", "true", "false", "on", "off", "null", "~",
+    ];
+
+    let plain = value
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '/')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '/' | '-'))
+        && !RESERVED_WORDS.contains(&value.to_lowercase().as_str());
+
+    if plain {
+        value.to_string()
+    } else {
+        yaml_quote(value)
+    }
+}
+
+/// Quote a value as a TOML basic string, which is always read back as a string
+pub fn toml_quote(value: &str) -> String {
+    let mut result = String::from('"');
+
+    for ch in value.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04X}", c as u32)),
+            c => result.push(c),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    // ==================== DOCKER TESTS ====================
+
+    #[test]
+    fn test_docker_values_are_literal() {
+        let output = format_docker(&vars(&[
+            ("PLAIN", "value"),
+            ("QUOTED", "\"keep quotes\""),
+            ("SPACES", "  padded # not a comment "),
+            ("DOLLAR", "$HOME"),
+            ("EMPTY", ""),
+        ]))
+        .unwrap();
+        assert_eq!(
+            output,
+            "PLAIN=value\nQUOTED=\"keep quotes\"\nSPACES=  padded # not a comment \nDOLLAR=$HOME\nEMPTY=\n"
+        );
+    }
+
+    #[test]
+    fn test_docker_rejects_line_breaks() {
+        let err =
+            format_docker(&vars(&[("OK", "1"), ("PEM", "a\nb"), ("CRLF", "a\r\nb")])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Docker env files cannot contain values with line breaks: PEM, CRLF"
+        );
+    }
+
+    // ==================== COMPOSE TESTS ====================
+
+    #[test]
+    fn test_compose_environment_mapping() {
+        let output = format_compose(&vars(&[
+            ("PORT", "8080"),
+            ("DEBUG", "true"),
+            ("URL", "postgres://u:p@db/app?sslmode=disable#x"),
+            ("PRICE", "$5"),
+            ("MULTI", "a\n\"b\""),
+        ]));
+        assert_eq!(
+            output,
+            "environment:\n  PORT: \"8080\"\n  DEBUG: \"true\"\n  \
+             URL: \"postgres://u:p@db/app?sslmode=disable#x\"\n  PRICE: \"$$5\"\n  \
+             MULTI: \"a\\n\\\"b\\\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_compose_empty() {
+        assert_eq!(format_compose(&[]), "environment:\n");
+    }
+
+    // ==================== OTHER SHELL TESTS ====================
+
+    /// Values that would run a command, expand a variable or end the string early if a
+    /// shell saw them unescaped; shared by every shell's escaper tests
+    const HOSTILE_VALUES: &[&str] = &[
+        "$(whoami)",
+        "`whoami`",
+        "; rm -rf /",
+        "| cat /etc/passwd",
+        "&& ls -la",
+        "$HOME ${HOME} $env:HOME",
+        "'; touch pwned; echo '",
+        "\"; touch pwned; echo \"",
+        "it's",
+        "back\\slash\\",
+        "line1\nline2",
+        "(echo hi)",
+        "{a,b} *.txt [a-z] ~",
+        "!! !$",
+        "value#comment",
+        "",
+    ];
+
+    const HOSTILE_KEYS: &[&str] = &[
+        "A$(touch pwned)",
+        "A`touch pwned`",
+        "A;touch pwned",
+        "A B",
+        "MY-KEY",
+        "A.B",
+        "env:PATH",
+        "1ABC",
+        "",
+    ];
+
+    #[test]
+    fn test_shell_formats_skip_hostile_keys() {
+        let env_vars: Vec<(String, String)> = HOSTILE_KEYS
+            .iter()
+            .map(|key| (key.to_string(), "1".to_string()))
+            .chain([("SAFE".to_string(), "1".to_string())])
+            .collect();
+
+        assert_eq!(invalid_shell_keys(&env_vars), HOSTILE_KEYS);
+        assert_eq!(format_sh(&env_vars), "export SAFE=1\n");
+        assert_eq!(format_fish(&env_vars), "set -gx SAFE '1'\n");
+        assert_eq!(format_powershell(&env_vars), "$env:SAFE = '1'\n");
+    }
+
+    #[test]
+    fn test_sh_escape() {
+        assert_eq!(sh_escape("simple-1.2/x:y@z"), "simple-1.2/x:y@z");
+        assert_eq!(sh_escape(""), "''");
+        assert_eq!(sh_escape("with space"), "'with space'");
+        assert_eq!(sh_escape("$(whoami)"), "'$(whoami)'");
+        assert_eq!(sh_escape("it's"), "'it'\\''s'");
+        assert_eq!(sh_escape("!!"), "'!!'");
+        assert_eq!(sh_escape("a\nb"), "'a\nb'");
+    }
+
+    #[test]
+    fn test_sh_escape_injection_resistance() {
+        for value in HOSTILE_VALUES {
+            let escaped = sh_escape(value);
+            // Inside single quotes nothing is special; the only way out is `'\''`
+            let inner = &escaped[1..escaped.len() - 1];
+            assert!(
+                escaped.starts_with('\'') && escaped.ends_with('\''),
+                "{escaped}"
+            );
+            assert_eq!(
+                inner.replace("'\\''", ""),
+                value.replace('\'', ""),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_sh_output() {
+        assert_eq!(
+            format_sh(&vars(&[("A", "1"), ("B", "x y")])),
+            "export A=1\nexport B='x y'\n"
+        );
+    }
+
+    #[test]
+    fn test_fish_escape() {
+        assert_eq!(fish_escape("simple"), "'simple'");
+        assert_eq!(fish_escape(""), "''");
+        assert_eq!(fish_escape("$HOME (echo hi)"), "'$HOME (echo hi)'");
+        assert_eq!(fish_escape("it's"), "'it\\'s'");
+        assert_eq!(fish_escape("a\\b"), "'a\\\\b'");
+        assert_eq!(fish_escape("a\nb"), "'a\nb'");
+    }
+
+    #[test]
+    fn test_fish_escape_injection_resistance() {
+        for value in HOSTILE_VALUES {
+            let escaped = fish_escape(value);
+            let inner = &escaped[1..escaped.len() - 1];
+            assert!(
+                escaped.starts_with('\'') && escaped.ends_with('\''),
+                "{escaped}"
+            );
+            // Every quote and backslash inside is escaped, so the string cannot end early
+            assert_eq!(unescape_backslashes(inner, '\''), *value, "{escaped}");
+        }
+    }
+
+    #[test]
+    fn test_fish_output() {
+        assert_eq!(
+            format_fish(&vars(&[("A", "1"), ("B", "x y")])),
+            "set -gx A '1'\nset -gx B 'x y'\n"
+        );
+    }
+
+    #[test]
+    fn test_powershell_escape() {
+        assert_eq!(powershell_escape("simple"), "'simple'");
+        assert_eq!(powershell_escape(""), "''");
+        assert_eq!(
+            powershell_escape("$env:HOME $(whoami)"),
+            "'$env:HOME $(whoami)'"
+        );
+        assert_eq!(powershell_escape("it's"), "'it''s'");
+        assert_eq!(powershell_escape("it\u{2019}s"), "'it\u{2019}\u{2019}s'");
+        assert_eq!(powershell_escape("back`tick\\"), "'back`tick\\'");
+    }
+
+    #[test]
+    fn test_powershell_escape_injection_resistance() {
+        for value in HOSTILE_VALUES {
+            let escaped = powershell_escape(value);
+            let inner = &escaped[1..escaped.len() - 1];
+            assert!(
+                escaped.starts_with('\'') && escaped.ends_with('\''),
+                "{escaped}"
+            );
+            assert_eq!(inner.replace("''", "'"), *value, "{escaped}");
+        }
+    }
+
+    #[test]
+    fn test_powershell_output() {
+        assert_eq!(
+            format_powershell(&vars(&[("A", "1"), ("B", "x y")])),
+            "$env:A = '1'\n$env:B = 'x y'\n"
+        );
+    }
+
+    #[test]
+    fn test_nu_escape() {
+        assert_eq!(nu_escape("simple"), "\"simple\"");
+        assert_eq!(nu_escape(""), "\"\"");
+        assert_eq!(nu_escape("$env.HOME (whoami)"), "\"$env.HOME (whoami)\"");
+        assert_eq!(nu_escape("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(nu_escape("a\\b"), "\"a\\\\b\"");
+        assert_eq!(nu_escape("a\nb\tc"), "\"a\\nb\\tc\"");
+        assert_eq!(nu_escape("esc\u{1b}"), "\"esc\\u{1b}\"");
+    }
+
+    #[test]
+    fn test_nu_escape_injection_resistance() {
+        for value in HOSTILE_VALUES {
+            let escaped = nu_escape(value);
+            let inner = &escaped[1..escaped.len() - 1];
+            assert!(
+                escaped.starts_with('"') && escaped.ends_with('"'),
+                "{escaped}"
+            );
+            assert!(!inner.contains(['\n', '\r']), "{escaped}");
+            assert_eq!(unescape_backslashes(inner, '"'), *value, "{escaped}");
+        }
+    }
+
+    #[test]
+    fn test_nu_output() {
+        assert_eq!(format_nu(&[]), "load-env {}\n");
+        assert_eq!(
+            format_nu(&vars(&[("A", "1"), ("B", "x y")])),
+            "load-env {\n    A: \"1\"\n    B: \"x y\"\n}\n"
+        );
+        assert_eq!(
+            format_nu(&vars(&[("A.B", "1"), ("my key", "2")])),
+            "load-env {\n    \"A.B\": \"1\"\n    \"my key\": \"2\"\n}\n"
+        );
+    }
+
+    /// Undo the backslash escapes the fish and nu escapers produce, failing if an
+    /// unescaped `quote` would have ended the string early
+    fn unescape_backslashes(inner: &str, quote: char) -> String {
+        let mut result = String::new();
+        let mut chars = inner.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => match chars.next().expect("dangling backslash") {
+                    'n' => result.push('\n'),
+                    'r' => result.push('\r'),
+                    't' => result.push('\t'),
+                    c => result.push(c),
+                },
+                c if c == quote => panic!("unescaped quote in {inner}"),
+                c => result.push(c),
+            }
+        }
+        result
+    }
+
+    // ==================== SYSTEMD TESTS ====================
+
+    #[test]
+    fn test_systemd_environment_file() {
+        let output = format_systemd(&vars(&[
+            ("PORT", "8080"),
+            ("URL", "postgres://u@db:5432/app"),
+            ("SPACES", "two words"),
+            ("SPECIAL", "a\"b\\c`d$e"),
+            ("MULTI", "line1\nline2"),
+            ("SINGLE", "it's"),
+            ("EMPTY", ""),
+        ]));
+        assert_eq!(
+            output,
+            "PORT=8080\nURL=postgres://u@db:5432/app\nSPACES=\"two words\"\n\
+             SPECIAL=\"a\\\"b\\\\c\\`d\\$e\"\nMULTI=\"line1\nline2\"\nSINGLE=\"it's\"\nEMPTY=\n"
+        );
+    }
+
+    #[test]
+    fn test_systemd_escape_injection_resistance() {
+        // Every character systemd treats specially must end up escaped or quoted
+        assert_eq!(systemd_escape("$(rm -rf /)"), "\"\\$(rm -rf /)\"");
+        assert_eq!(systemd_escape("`id`"), "\"\\`id\\`\"");
+        assert_eq!(systemd_escape("a\" B=injected"), "\"a\\\" B=injected\"");
+        assert_eq!(systemd_escape("# comment"), "\"# comment\"");
+        assert_eq!(systemd_escape("trailing\\"), "\"trailing\\\\\"");
+        assert_eq!(systemd_escape("; semi"), "\"; semi\"");
+    }
+
+    #[test]
+    fn test_systemd_dropin() {
+        let output = format_systemd_dropin(&vars(&[
+            ("PORT", "8080"),
+            ("GREETING", "hello \"world\""),
+            ("PERCENT", "100%h"),
+            ("PATHS", "C:\\tmp;$HOME"),
+            ("MULTI", "a\nb\tc"),
+        ]));
+        assert_eq!(
+            output,
+            "[Service]\nEnvironment=\"PORT=8080\"\n\
+             Environment=\"GREETING=hello \\\"world\\\"\"\n\
+             Environment=\"PERCENT=100%%h\"\n\
+             Environment=\"PATHS=C:\\\\tmp;$HOME\"\n\
+             Environment=\"MULTI=a\\nb\\tc\"\n"
+        );
+    }
+
+    #[test]
+    fn test_systemd_dropin_escape_control_characters() {
+        assert_eq!(systemd_dropin_escape("A=\u{1b}[0m"), "\"A=\\x1b[0m\"");
+        assert_eq!(systemd_dropin_escape("A=✓"), "\"A=✓\"");
+    }
+
+    // ==================== KUBERNETES TESTS ====================
+
+    fn metadata(namespace: Option<&str>) -> ManifestMetadata {
+        ManifestMetadata::new("my-app", namespace).unwrap()
+    }
+
+    #[test]
+    fn test_k8s_configmap() {
+        let output = format_k8s_configmap(
+            &vars(&[("PORT", "8080"), ("ENABLED", "yes"), ("NOTE", "a: b # c")]),
+            &metadata(Some("prod")),
+        );
+        assert_eq!(
+            output,
+            "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: my-app\n  namespace: prod\n\
+             data:\n  PORT: \"8080\"\n  ENABLED: \"yes\"\n  NOTE: \"a: b # c\"\n"
+        );
+    }
+
+    #[test]
+    fn test_k8s_secret_is_base64_encoded() {
+        let output = format_k8s_secret(
+            &vars(&[("DB_PASSWORD", "hunter2"), ("EMPTY", ""), ("PEM", "a\nb")]),
+            &metadata(None),
+        );
+        assert_eq!(
+            output,
+            "apiVersion: v1\nkind: Secret\nmetadata:\n  name: my-app\ntype: Opaque\n\
+             data:\n  DB_PASSWORD: aHVudGVyMg==\n  EMPTY: \"\"\n  PEM: YQpi\n"
+        );
+    }
+
+    #[test]
+    fn test_k8s_empty_data() {
+        assert!(format_k8s_configmap(&[], &metadata(None)).ends_with("data: {}\n"));
+        assert!(format_k8s_secret(&[], &metadata(None)).ends_with("data: {}\n"));
+    }
+
+    #[test]
+    fn test_k8s_metadata_validation() {
+        assert!(ManifestMetadata::new("app.example-1", Some("team-a")).is_ok());
+        assert!(ManifestMetadata::new("My_App", None).is_err());
+        assert!(ManifestMetadata::new("-app", None).is_err());
+        assert!(ManifestMetadata::new("", None).is_err());
+        assert!(ManifestMetadata::new("app", Some("team.a")).is_err());
+        assert!(ManifestMetadata::new(&"a".repeat(64), None).is_err());
+    }
+
+    #[test]
+    fn test_k8s_name_from_file() {
+        assert_eq!(ManifestMetadata::name_from_file(".env"), "env");
+        assert_eq!(
+            ManifestMetadata::name_from_file(".env.production"),
+            "env-production"
+        );
+        assert_eq!(ManifestMetadata::name_from_file("My_App.env"), "my-app-env");
+        assert_eq!(ManifestMetadata::name_from_file("..."), "env");
+    }
+
+    #[test]
+    fn test_key_matches() {
+        assert!(key_matches("*_PASSWORD", "DB_PASSWORD"));
+        assert!(key_matches("*_PASSWORD", "_PASSWORD"));
+        assert!(!key_matches("*_PASSWORD", "DB_PASSWORD_FILE"));
+        assert!(key_matches("API_*", "API_KEY"));
+        assert!(key_matches("*SECRET*", "MY_SECRET_VALUE"));
+        assert!(key_matches("KEY?", "KEY1"));
+        assert!(!key_matches("KEY?", "KEY"));
+        assert!(key_matches("*", ""));
+        assert!(key_matches("A*B*C", "AXXBYYBC"));
+        assert!(!key_matches("A*B*C", "AXXBYY"));
+        assert!(key_matches("EXACT", "EXACT"));
+        assert!(!key_matches("exact", "EXACT"));
+    }
+
+    #[test]
+    fn test_partition_by_patterns() {
+        let (config, secret) = partition_by_patterns(
+            vars(&[
+                ("HOST", "h"),
+                ("DB_PASSWORD", "p"),
+                ("PORT", "1"),
+                ("API_SECRET", "s"),
+            ]),
+            &["*_PASSWORD".to_string(), "*_SECRET".to_string()],
+        );
+        assert_eq!(config, vars(&[("HOST", "h"), ("PORT", "1")]));
+        assert_eq!(secret, vars(&[("DB_PASSWORD", "p"), ("API_SECRET", "s")]));
+    }
+
+    // ==================== YAML QUOTING TESTS ====================
+
+    #[test]
+    fn test_yaml_quote_escapes() {
+        assert_eq!(yaml_quote("plain"), "\"plain\"");
+        assert_eq!(yaml_quote("a\\b"), "\"a\\\\b\"");
+        assert_eq!(yaml_quote("tab\there"), "\"tab\\there\"");
+        assert_eq!(yaml_quote("bell\u{7}"), "\"bell\\x07\"");
+        assert_eq!(yaml_quote("sep\u{85}"), "\"sep\\x85\"");
+        assert_eq!(yaml_quote("unicode ✓"), "\"unicode ✓\"");
+    }
+
+    #[test]
+    fn test_yaml_scalar_plain_and_quoted() {
+        for plain in [
+            "HOST",
+            "value",
+            "some_path/to/file.txt",
+            "/usr/bin",
+            "v1.2-rc",
+            "NONE",
+        ] {
+            assert_eq!(yaml_scalar(plain), plain);
+        }
+        for ambiguous in [
+            "",
+            "Y",
+            "NO",
+            "On",
+            "true",
+            "False",
+            "null",
+            "~",
+            "123",
+            "0x1F",
+            "1e3",
+            "-5",
+            "+1",
+            ".inf",
+            "2024-01-01",
+            "1:30",
+            "a b",
+            "key: value",
+            "# comment",
+            "*alias",
+            "&anchor",
+            "!tag",
+            "@at",
+            "`tick",
+            "[list]",
+            "{map}",
+            "> folded",
+            "| literal",
+            "'single'",
+            "\"double\"",
+            "%directive",
+            "trailing ",
+            "a\nb",
+        ] {
+            assert_eq!(yaml_scalar(ambiguous), yaml_quote(ambiguous), "{ambiguous}");
+        }
+    }
+
+    // ==================== YAML AND TOML TESTS ====================
+
+    #[test]
+    fn test_yaml_output() {
+        assert_eq!(format_yaml(&[]), "{}\n");
+        assert_eq!(
+            format_yaml(&vars(&[
+                ("HOST", "localhost"),
+                ("PORT", "8080"),
+                ("DEBUG", "yes"),
+                ("URL", "http://x:1/a?b=c#d"),
+                ("Y", "n"),
+            ])),
+            "HOST: localhost\nPORT: \"8080\"\nDEBUG: \"yes\"\nURL: \"http://x:1/a?b=c#d\"\n\"Y\": \"n\"\n"
+        );
+    }
+
+    #[test]
+    fn test_yaml_output_reads_back_as_strings() {
+        let env_vars = vars(&[
+            ("A", "true"),
+            ("B", "0o17"),
+            ("C", "1_000"),
+            ("D", "- item"),
+            ("E", "line1\nline2\r\n"),
+            ("F", "quote \" and \\ backslash"),
+            ("G", "sep\u{2028}bom\u{feff}nel\u{85}bell\u{7}"),
+            ("H", " padded "),
+            ("I", "ünïcødé ✓"),
+            ("J", ""),
+            ("NO", "null"),
+        ]);
+        let parsed: std::collections::BTreeMap<String, String> =
+            serde_norway::from_str(&format_yaml(&env_vars)).unwrap();
+        assert_eq!(parsed, env_vars.into_iter().collect());
+    }
+
+    #[test]
+    fn test_toml_output() {
+        assert_eq!(format_toml(&[]), "");
+        assert_eq!(
+            format_toml(&vars(&[
+                ("PORT", "8080"),
+                ("DEBUG", "true"),
+                ("MSG", "a \"b\"\n")
+            ])),
+            "PORT = \"8080\"\nDEBUG = \"true\"\nMSG = \"a \\\"b\\\"\\n\"\n"
+        );
+        assert_eq!(toml_quote("del\u{7f}"), "\"del\\u007F\"");
+        assert_eq!(
+            format_toml(&vars(&[("A.B", "1"), ("kebab-key", "2")])),
+            "\"A.B\" = \"1\"\nkebab-key = \"2\"\n"
+        );
+    }
+
+    #[test]
+    fn test_toml_output_reads_back_as_strings() {
+        let env_vars = vars(&[
+            ("A", "true"),
+            ("B", "1979-05-27"),
+            ("C", "inf"),
+            ("D", "C:\\path\\to"),
+            ("E", "line1\nline2\r\n\ttab"),
+            ("F", "esc\u{1b}[0m del\u{7f}"),
+            ("G", "'single' = #hash"),
+            ("H", "ünïcødé ✓"),
+            ("I", ""),
+            ("A.B", "dotted"),
+            ("key with spaces", "x"),
+            ("\"quoted\"", "y"),
+        ]);
+        let parsed: std::collections::BTreeMap<String, String> =
+            toml::from_str(&format_toml(&env_vars)).unwrap();
+        assert_eq!(parsed, env_vars.into_iter().collect());
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.
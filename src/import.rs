@@ -0,0 +1,506 @@
+// Importing variables from other sources into an env file.
+//
+// Each reader turns a source into the same ordered key/value list the export side uses:
+// - JSON objects and YAML maps, with nested values flattened into `PARENT__CHILD` keys
+//   (numbers and booleans become their text)
+// - Docker `--env-file` files, whose values are taken literally
+// - Other env files or `KEY=value` lines on standard input
+// - The current process environment, filtered by a prefix
+//
+// `import_env_vars` merges the result into parsed lines through `set_env_vars_with_policy`,
+// so existing lines keep their formatting and new keys are appended. `plan_import` does the
+// comparison on its own, so callers can encrypt the new values of encrypted keys first.
+
+use crate::{
+    flatten_value, get_env_vars_with_policy, is_encrypted, is_valid_key,
+    parse_env_file_with_diagnostics, set_env_vars_with_policy, DuplicatePolicy, EncryptionKey,
+    EnvLine, FlattenOptions, OrderedEnvVars, Severity,
+};
+use anyhow::{Context, Result};
+use serde_norway::Value as YamlValue;
+use std::env;
+
+/// What to do when an imported key already has a different value in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeMode {
+    /// Refuse to import, listing the conflicting keys
+    Strict,
+    /// Replace existing values with imported ones
+    Overwrite,
+    /// Leave existing values alone
+    KeepExisting,
+}
+
+/// Which keys an import added, changed, left unchanged, or skipped because the file
+/// already had a different value, in import order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub kept: Vec<String>,
+}
+
+impl ImportSummary {
+    /// Whether the import modified the file
+    pub fn has_changes(&self) -> bool {
+        !self.added.is_empty() || !self.changed.is_empty()
+    }
+}
+
+/// Merge `imported` into `lines` according to `mode`
+pub fn import_env_vars(
+    lines: &mut Vec<EnvLine>,
+    imported: OrderedEnvVars,
+    mode: MergeMode,
+    policy: DuplicatePolicy,
+) -> Result<ImportSummary> {
+    let (summary, pairs) = plan_import(lines, imported, mode, policy, None)?;
+    set_env_vars_with_policy(lines, pairs, policy)?;
+    Ok(summary)
+}
+
+/// Work out what merging `imported` into `lines` would do, returning the summary and the
+/// `KEY=value` pairs to set. Encrypted current values are decrypted with `encryption_key`
+/// before comparing; without a key they always count as different.
+pub fn plan_import(
+    lines: &[EnvLine],
+    imported: OrderedEnvVars,
+    mode: MergeMode,
+    policy: DuplicatePolicy,
+    encryption_key: Option<&EncryptionKey>,
+) -> Result<(ImportSummary, Vec<String>)> {
+    let mut existing = get_env_vars_with_policy(lines, policy)?;
+    if let Some(encryption_key) = encryption_key {
+        for (key, value) in existing.iter_mut() {
+            if is_encrypted(value) {
+                *value = encryption_key.decrypt(key, value)?;
+            }
+        }
+    }
+    let mut summary = ImportSummary::default();
+    let mut pairs = Vec::new();
+
+    for (key, value) in imported {
+        match existing.get(&key) {
+            None => summary.added.push(key.clone()),
+            Some(current) if *current == value => {
+                summary.unchanged.push(key);
+                continue;
+            }
+            Some(_) if mode == MergeMode::KeepExisting => {
+                summary.kept.push(key);
+                continue;
+            }
+            Some(_) => summary.changed.push(key.clone()),
+        }
+        pairs.push(format!("{key}={value}"));
+    }
+
+    if mode == MergeMode::Strict && !summary.changed.is_empty() {
+        anyhow::bail!(
+            "Imported values differ from existing ones for: {} (use --overwrite or --keep-existing)",
+            summary.changed.join(", ")
+        );
+    }
+
+    Ok((summary, pairs))
+}
+
+/// Parse `content` as `format` (json, yaml, docker or env), flattening nested JSON and
+/// YAML values as `flatten` describes
+pub fn parse_import(
+    content: &str,
+    format: &str,
+    flatten: &FlattenOptions,
+) -> Result<OrderedEnvVars> {
+    let vars = match format.to_lowercase().as_str() {
+        "json" => parse_json(content, flatten)?,
+        "yaml" | "yml" => parse_yaml(content, flatten)?,
+        "docker" => parse_docker(content),
+        "env" => parse_env(content)?,
+        _ => anyhow::bail!(
+            "Unsupported import format: {}. Use 'json', 'yaml', 'docker' or 'env'",
+            format
+        ),
+    };
+    validated(vars)
+}
+
+/// Variables from the process environment whose names start with `prefix`, sorted by
+/// name, optionally with the prefix removed. Variables that are not valid UTF-8 or whose
+/// names cannot be written to an env file are skipped.
+pub fn process_env_vars(prefix: &str, strip_prefix: bool) -> Result<OrderedEnvVars> {
+    let mut vars: OrderedEnvVars = env::vars_os()
+        .filter_map(|(key, value)| {
+            let (key, value) = (key.into_string().ok()?, value.into_string().ok()?);
+            let stripped = key.strip_prefix(prefix)?;
+            let key = if strip_prefix { stripped } else { &key };
+            is_valid_key(key).then(|| (key.to_string(), value))
+        })
+        .collect();
+    vars.sort();
+    validated(vars)
+}
+
+fn parse_json(content: &str, flatten: &FlattenOptions) -> Result<OrderedEnvVars> {
+    let value: serde_json::Value = serde_json::from_str(content).context("Failed to parse JSON")?;
+    flatten_value(&value, flatten)
+}
+
+fn parse_yaml(content: &str, flatten: &FlattenOptions) -> Result<OrderedEnvVars> {
+    let value: YamlValue = serde_norway::from_str(content).context("Failed to parse YAML")?;
+    match value {
+        // An empty document
+        YamlValue::Null => Ok(Vec::new()),
+        value => flatten_value(&yaml_to_json(value)?, flatten),
+    }
+}
+
+/// Convert YAML to JSON for flattening; scalars only need their text, and scalar
+/// keys of any type become strings
+fn yaml_to_json(value: YamlValue) -> Result<serde_json::Value> {
+    Ok(match value {
+        YamlValue::Mapping(mapping) => serde_json::Value::Object(
+            mapping
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = yaml_scalar_text(key).context("YAML keys must be scalars")?;
+                    Ok((key, yaml_to_json(value)?))
+                })
+                .collect::<Result<_>>()?,
+        ),
+        YamlValue::Sequence(items) => {
+            serde_json::Value::Array(items.into_iter().map(yaml_to_json).collect::<Result<_>>()?)
+        }
+        YamlValue::Tagged(tagged) => {
+            anyhow::bail!("Unsupported YAML tag: {}", tagged.tag)
+        }
+        scalar => serde_json::Value::String(yaml_scalar_text(scalar).unwrap_or_default()),
+    })
+}
+
+fn yaml_scalar_text(value: YamlValue) -> Option<String> {
+    match value {
+        YamlValue::String(s) => Some(s),
+        YamlValue::Null => Some(String::new()),
+        YamlValue::Bool(b) => Some(b.to_string()),
+        YamlValue::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Parse a Docker env-file: everything after the first `=` is the value, verbatim. A
+/// bare `KEY` line passes the variable through from the environment, as Docker does.
+fn parse_docker(content: &str) -> OrderedEnvVars {
+    content
+        .lines()
+        .map(str::trim_start)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match line.split_once('=') {
+            Some((key, value)) => Some((key.to_string(), value.to_string())),
+            None => {
+                let key = line.trim_end();
+                env::var(key).ok().map(|value| (key.to_string(), value))
+            }
+        })
+        .collect()
+}
+
+fn parse_env(content: &str) -> Result<OrderedEnvVars> {
+    let (lines, diagnostics) = parse_env_file_with_diagnostics(content);
+    if let Some(error) = diagnostics
+        .iter()
+        .find(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        anyhow::bail!("Failed to parse env input: {error}");
+    }
+
+    Ok(lines
+        .into_iter()
+        .filter_map(|line| match line {
+            EnvLine::KeyValue { key, value, .. } => Some((key, value)),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Reject keys that cannot be written to an env file, and collapse repeated keys so the
+/// last value wins at the position of the first
+fn validated(vars: OrderedEnvVars) -> Result<OrderedEnvVars> {
+    let invalid: Vec<&str> = vars
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .filter(|key| !is_valid_key(key))
+        .collect();
+    if !invalid.is_empty() {
+        anyhow::bail!("Invalid variable name(s): {}", invalid.join(", "));
+    }
+
+    let mut result: OrderedEnvVars = Vec::with_capacity(vars.len());
+    for (key, value) in vars {
+        match result.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = value,
+            None => result.push((key, value)),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_env_file, write_env_file};
+    use pretty_assertions::assert_eq;
+
+    fn vars(pairs: &[(&str, &str)]) -> OrderedEnvVars {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn parse(content: &str, format: &str) -> Result<OrderedEnvVars> {
+        parse_import(content, format, &FlattenOptions::default())
+    }
+
+    // ==================== SOURCE PARSING TESTS ====================
+
+    #[test]
+    fn test_parse_json_object() {
+        let imported = parse(
+            r#"{"ZED": "z", "PORT": 8080, "DEBUG": true, "RATE": 0.5, "EMPTY": null}"#,
+            "json",
+        )
+        .unwrap();
+        assert_eq!(
+            imported,
+            vars(&[
+                ("ZED", "z"),
+                ("PORT", "8080"),
+                ("DEBUG", "true"),
+                ("RATE", "0.5"),
+                ("EMPTY", ""),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_json_rejects_non_objects() {
+        assert!(parse("[1, 2]", "json").is_err());
+        assert!(parse("not json", "json").is_err());
+    }
+
+    #[test]
+    fn test_parse_flattens_nested_values() {
+        let flatten = FlattenOptions {
+            separator: "__".to_string(),
+            uppercase: true,
+        };
+        let imported = parse_import(
+            r#"{"db": {"host": "x", "replicas": ["a", "b"]}, "port": 1}"#,
+            "json",
+            &flatten,
+        )
+        .unwrap();
+        assert_eq!(
+            imported,
+            vars(&[
+                ("DB__HOST", "x"),
+                ("DB__REPLICAS__0", "a"),
+                ("DB__REPLICAS__1", "b"),
+                ("PORT", "1"),
+            ])
+        );
+
+        let imported =
+            parse_import("db:\n  host: x\n  tls: {enabled: true}\n", "yaml", &flatten).unwrap();
+        assert_eq!(
+            imported,
+            vars(&[("DB__HOST", "x"), ("DB__TLS__ENABLED", "true")])
+        );
+
+        let err = parse("db:\n  host-name: x\n", "yaml").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid variable name(s): db__host-name");
+    }
+
+    #[test]
+    fn test_parse_yaml_map() {
+        let imported = parse(
+            "HOST: localhost\nPORT: 5432\nSSL: yes\nDEBUG: false\nNOTE: \"a: b\"\nEMPTY:\n",
+            "yaml",
+        )
+        .unwrap();
+        assert_eq!(
+            imported,
+            vars(&[
+                ("HOST", "localhost"),
+                ("PORT", "5432"),
+                ("SSL", "yes"),
+                ("DEBUG", "false"),
+                ("NOTE", "a: b"),
+                ("EMPTY", ""),
+            ])
+        );
+        assert_eq!(parse("", "yml").unwrap(), vec![]);
+        assert!(parse("- a\n- b\n", "yaml").is_err());
+        assert!(parse("TAGGED: !custom x\n", "yaml").is_err());
+    }
+
+    #[test]
+    fn test_parse_docker_env_file() {
+        env::set_var("DOTENV_IMPORT_DOCKER_PASSTHROUGH", "from-env");
+        let imported = parse(
+            "# comment\n\n  NAME=\"quoted\" # kept\nDOTENV_IMPORT_DOCKER_PASSTHROUGH\nUNSET_DOTENV_IMPORT_VAR\nEQ=a=b\n",
+            "docker",
+        )
+        .unwrap();
+        assert_eq!(
+            imported,
+            vars(&[
+                ("NAME", "\"quoted\" # kept"),
+                ("DOTENV_IMPORT_DOCKER_PASSTHROUGH", "from-env"),
+                ("EQ", "a=b"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_env_lines() {
+        let imported = parse("export A=1\nB=\"two words\" # c\nA=3\n", "env").unwrap();
+        assert_eq!(imported, vars(&[("A", "3"), ("B", "two words")]));
+
+        let err = parse("A=\"unterminated\n", "env").unwrap_err();
+        assert!(err.to_string().contains("unterminated"));
+    }
+
+    #[test]
+    fn test_parse_import_rejects_invalid_keys() {
+        let err = parse(r#"{"OK": "1", "my-key": "2", "1X": "3"}"#, "json").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid variable name(s): my-key, 1X");
+        assert!(parse("{}", "xml").is_err());
+    }
+
+    #[test]
+    fn test_process_env_vars_prefix() {
+        env::set_var("DOTENV_IMPORT_PREFIX_B", "2");
+        env::set_var("DOTENV_IMPORT_PREFIX_A", "1");
+
+        assert_eq!(
+            process_env_vars("DOTENV_IMPORT_PREFIX_", false).unwrap(),
+            vars(&[
+                ("DOTENV_IMPORT_PREFIX_A", "1"),
+                ("DOTENV_IMPORT_PREFIX_B", "2")
+            ])
+        );
+        assert_eq!(
+            process_env_vars("DOTENV_IMPORT_PREFIX_", true).unwrap(),
+            vars(&[("A", "1"), ("B", "2")])
+        );
+    }
+
+    // ==================== MERGE TESTS ====================
+
+    #[test]
+    fn test_import_summary_and_merge() {
+        let mut lines = parse_env_file("# keep me\nSAME=1\nDIFF='old'\n");
+        let summary = import_env_vars(
+            &mut lines,
+            vars(&[("SAME", "1"), ("DIFF", "new"), ("NEW", "x y")]),
+            MergeMode::Overwrite,
+            DuplicatePolicy::LastWins,
+        )
+        .unwrap();
+
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: vec!["NEW".to_string()],
+                changed: vec!["DIFF".to_string()],
+                unchanged: vec!["SAME".to_string()],
+                kept: vec![],
+            }
+        );
+        assert!(summary.has_changes());
+        assert_eq!(
+            write_env_file(&lines),
+            "# keep me\nSAME=1\nDIFF='new'\nNEW=\"x y\"\n"
+        );
+    }
+
+    #[test]
+    fn test_import_keep_existing() {
+        let mut lines = parse_env_file("DIFF=old\n");
+        let summary = import_env_vars(
+            &mut lines,
+            vars(&[("DIFF", "new"), ("NEW", "1")]),
+            MergeMode::KeepExisting,
+            DuplicatePolicy::LastWins,
+        )
+        .unwrap();
+
+        assert_eq!(summary.kept, vec!["DIFF"]);
+        assert_eq!(summary.added, vec!["NEW"]);
+        assert_eq!(write_env_file(&lines), "DIFF=old\nNEW=1\n");
+    }
+
+    #[test]
+    fn test_import_strict_refuses_conflicts() {
+        let mut lines = parse_env_file("A=old\nB=same\n");
+        let err = import_env_vars(
+            &mut lines,
+            vars(&[("A", "new"), ("B", "same"), ("C", "1")]),
+            MergeMode::Strict,
+            DuplicatePolicy::LastWins,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("differ from existing ones for: A"));
+        assert_eq!(write_env_file(&lines), "A=old\nB=same\n");
+
+        let summary = import_env_vars(
+            &mut lines,
+            vars(&[("B", "same")]),
+            MergeMode::Strict,
+            DuplicatePolicy::LastWins,
+        )
+        .unwrap();
+        assert!(!summary.has_changes());
+    }
+
+    #[test]
+    fn test_plan_import_compares_decrypted_values() {
+        let encryption_key = EncryptionKey::generate();
+        let content = format!(
+            "PIN={}\nTOKEN={}\n",
+            encryption_key.encrypt("PIN", "1234"),
+            encryption_key.encrypt("TOKEN", "old")
+        );
+        let lines = parse_env_file(&content);
+        let imported = vars(&[("PIN", "1234"), ("TOKEN", "new")]);
+
+        let (summary, pairs) = plan_import(
+            &lines,
+            imported.clone(),
+            MergeMode::Overwrite,
+            DuplicatePolicy::LastWins,
+            Some(&encryption_key),
+        )
+        .unwrap();
+        assert_eq!(summary.unchanged, vec!["PIN"]);
+        assert_eq!(summary.changed, vec!["TOKEN"]);
+        assert_eq!(pairs, vec!["TOKEN=new"]);
+
+        // Without the key the ciphertext cannot match, so both count as changed
+        let (summary, _) = plan_import(
+            &lines,
+            imported,
+            MergeMode::Overwrite,
+            DuplicatePolicy::LastWins,
+            None,
+        )
+        .unwrap();
+        assert_eq!(summary.changed, vec!["PIN", "TOKEN"]);
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.
@@ -2,7 +2,9 @@
 //
 // This module implements the command-line interface and dispatches commands to the appropriate
 // handlers in the library module. It provides subcommands for setting/unsetting environment
-// variables, generating secure passwords, listing keys, and exporting configurations.
+// variables, generating secure passwords, listing keys, checking syntax, exporting
+// configurations, importing variables from other sources, and running programs with the
+// file's variables in their environment.
 //
 // The CLI supports operations on custom .env files via the --file flag and maintains file
 // structure preservation while performing modifications. All operations include proper error
@@ -11,55 +13,486 @@
 use anyhow::{Context, Result};
 use clap::Parser;
 use durable_appconfig_dotenv::*;
-use std::path::PathBuf;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+use std::time::Duration;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let lock_timeout = Duration::from_secs(cli.lock_timeout);
+    let policy = cli.duplicates;
+    let key_file = cli.key_file.as_deref();
 
     match cli.command {
-        Commands::Set { pairs } => set_command(&cli.file, pairs),
-        Commands::Unset { keys } => unset_command(&cli.file, keys),
-        Commands::Export { format } => export_command(&cli.file, &format),
+        Commands::Set { pairs } => set_command(&cli.file, lock_timeout, policy, key_file, pairs),
+        Commands::Unset { keys } => unset_command(&cli.file, lock_timeout, policy, keys),
+        Commands::Export {
+            format,
+            expand,
+            process_env,
+            sort,
+            mask_secrets,
+            name,
+            namespace,
+            secret_keys,
+            nest,
+            separator,
+            infer_types,
+        } => export_command(
+            &cli.file,
+            policy,
+            key_file,
+            &format,
+            ExportOptions {
+                expand,
+                process_env,
+                sort,
+                mask_secrets,
+                name,
+                namespace,
+                secret_keys,
+                nest: nest.then_some(NestOptions {
+                    separator,
+                    infer_types,
+                }),
+            },
+        ),
+        Commands::Run {
+            extra_files,
+            no_override,
+            clean,
+            expand,
+            process_env,
+            command,
+        } => run_command(
+            &cli.file,
+            &extra_files,
+            policy,
+            key_file,
+            RunOptions {
+                no_override,
+                clean,
+                expand,
+                process_env,
+            },
+            &command,
+        ),
+        Commands::Get {
+            keys,
+            default,
+            json,
+            shell,
+        } => get_command(
+            &cli.file,
+            policy,
+            key_file,
+            &keys,
+            default.as_deref(),
+            json,
+            shell,
+        ),
         Commands::Keys => keys_command(&cli.file),
+        Commands::Check => check_command(&cli.file, policy),
+        Commands::Validate { schema } => {
+            validate_command(&cli.file, policy, key_file, schema.as_deref())
+        }
+        Commands::Import {
+            source,
+            format,
+            process_env,
+            prefix,
+            strip_prefix,
+            overwrite,
+            keep_existing,
+            separator,
+            uppercase,
+        } => {
+            let source = if process_env {
+                ImportSource::ProcessEnv {
+                    prefix: prefix.unwrap_or_default(),
+                    strip_prefix,
+                }
+            } else {
+                ImportSource::File {
+                    path: source.context("No import source given")?,
+                    format,
+                    flatten: FlattenOptions {
+                        separator,
+                        uppercase,
+                    },
+                }
+            };
+            let mode = if overwrite {
+                MergeMode::Overwrite
+            } else if keep_existing {
+                MergeMode::KeepExisting
+            } else {
+                MergeMode::Strict
+            };
+            import_command(&cli.file, lock_timeout, policy, key_file, source, mode)
+        }
+        Commands::Encrypt { keys } => {
+            encrypt_command(&cli.file, lock_timeout, policy, key_file, &keys)
+        }
+        Commands::Decrypt { keys } => {
+            decrypt_command(&cli.file, lock_timeout, policy, key_file, &keys)
+        }
+        Commands::Dedupe => dedupe_command(&cli.file, lock_timeout, policy),
+        Commands::Sync { template } => {
+            let template = template.unwrap_or_else(|| sidecar_path(&cli.file, ".env.example"));
+            sync_command(&cli.file, lock_timeout, policy, &template)
+        }
+        Commands::Example {
+            output,
+            placeholder,
+        } => example_command(&cli.file, output.as_ref(), placeholder),
+        Commands::Diff {
+            a,
+            b,
+            show_values,
+            keys_only,
+        } => diff_command(&a, &b, policy, show_values, keys_only),
         Commands::Randomize {
             keys,
             numeric,
             symbol,
             length,
             xkcd,
-        } => randomize_command(&cli.file, keys, numeric, symbol, length, xkcd),
+        } => randomize_command(
+            &cli.file,
+            lock_timeout,
+            policy,
+            key_file,
+            RandomizeOptions {
+                numeric,
+                symbol,
+                length,
+                xkcd,
+            },
+            keys,
+        ),
     }
 }
 
-fn set_command(file_path: &PathBuf, pairs: Vec<String>) -> Result<()> {
+fn set_command(
+    file_path: &PathBuf,
+    lock_timeout: Duration,
+    policy: DuplicatePolicy,
+    key_file: Option<&Path>,
+    pairs: Vec<String>,
+) -> Result<()> {
+    let _lock = lock_env_file(file_path, lock_timeout)?;
     let mut lines = read_env_file(file_path)?;
-    set_env_vars(&mut lines, pairs)?;
+    let encryption_key = find_encryption_key(file_path, key_file, &lines)?;
+    let pairs = encrypt_replacements(file_path, encryption_key.as_ref(), &lines, pairs, policy)?;
+    set_env_vars_with_policy(&mut lines, pairs, policy)?;
     save_env_file(file_path, &lines)
 }
 
-fn unset_command(file_path: &PathBuf, keys: Vec<String>) -> Result<()> {
+/// Whether any value in `lines` is encrypted
+fn has_encrypted_values(lines: &[EnvLine]) -> bool {
+    lines
+        .iter()
+        .any(|line| matches!(line, EnvLine::KeyValue { value, .. } if is_encrypted(value)))
+}
+
+/// The encryption key for `file_path`. It is only looked up when the file holds encrypted
+/// values, so a stray `DOTENV_KEY` cannot break plain edits.
+fn find_encryption_key(
+    file_path: &Path,
+    key_file: Option<&Path>,
+    lines: &[EnvLine],
+) -> Result<Option<EncryptionKey>> {
+    if !has_encrypted_values(lines) {
+        return Ok(None);
+    }
+    EncryptionKey::find(file_path, key_file)
+}
+
+/// Encrypt new values for keys whose current value is encrypted, refusing when no key
+/// was found
+fn encrypt_replacements(
+    file_path: &Path,
+    encryption_key: Option<&EncryptionKey>,
+    lines: &[EnvLine],
+    pairs: Vec<String>,
+    policy: DuplicatePolicy,
+) -> Result<Vec<String>> {
+    if encryption_key.is_some() {
+        return encrypt_replacement_pairs(lines, pairs, encryption_key, policy);
+    }
+    if !has_encrypted_values(lines) {
+        return Ok(pairs);
+    }
+    encrypt_replacement_pairs(lines, pairs, None, policy).with_context(|| {
+        format!(
+            "Refusing to write a plaintext value over an encrypted one (use --key-file, set \
+             {KEY_ENV_VAR} or create {})",
+            key_file_path(file_path).display()
+        )
+    })
+}
+
+fn unset_command(
+    file_path: &PathBuf,
+    lock_timeout: Duration,
+    policy: DuplicatePolicy,
+    keys: Vec<String>,
+) -> Result<()> {
+    let _lock = lock_env_file(file_path, lock_timeout)?;
     let mut lines = read_env_file(file_path)?;
-    unset_env_vars(&mut lines, keys);
+    unset_env_vars_with_policy(&mut lines, keys, policy)?;
     save_env_file(file_path, &lines)
 }
 
-fn export_command(file_path: &PathBuf, format: &str) -> Result<()> {
-    let lines = read_env_file(file_path)?;
-    let env_vars = get_env_vars(&lines);
+struct ExportOptions {
+    expand: bool,
+    process_env: bool,
+    sort: bool,
+    mask_secrets: bool,
+    name: Option<String>,
+    namespace: Option<String>,
+    secret_keys: Vec<String>,
+    nest: Option<NestOptions>,
+}
 
-    match format.to_lowercase().as_str() {
+struct NestOptions {
+    separator: String,
+    infer_types: bool,
+}
+
+fn export_command(
+    file_path: &PathBuf,
+    policy: DuplicatePolicy,
+    key_file: Option<&Path>,
+    format: &str,
+    options: ExportOptions,
+) -> Result<()> {
+    let lines = read_decrypted_env_file(file_path, key_file)?;
+    let env_vars = if options.expand {
+        expand_env_vars(&lines, options.process_env, policy)?
+    } else {
+        get_env_vars_with_policy(&lines, policy)?
+    };
+    let order = if options.sort {
+        KeyOrder::Sorted
+    } else {
+        KeyOrder::File
+    };
+    let mut env_vars = order_env_vars(&lines, env_vars, order);
+
+    if options.mask_secrets {
+        let (schema, _) = annotated_schema(&lines);
+        for (key, value) in &mut env_vars {
+            if schema.keys.get(key).is_some_and(|spec| spec.secret) {
+                *value = MASKED_VALUE.to_string();
+            }
+        }
+    }
+
+    let format = format.to_lowercase();
+    if options.nest.is_some() && format != "json" {
+        anyhow::bail!("--nest is only supported with --format json");
+    }
+
+    if matches!(
+        format.as_str(),
+        "bash" | "sh" | "zsh" | "fish" | "powershell" | "pwsh"
+    ) {
+        for key in invalid_shell_keys(&env_vars) {
+            eprintln!("warning: skipping '{key}': not a valid shell variable name");
+        }
+    }
+
+    match format.as_str() {
         "bash" => {
             for (key, value) in env_vars {
-                println!("export {key}={}", shell_escape(&value));
+                if is_valid_key(&key) {
+                    println!("export {key}={}", shell_escape(&value));
+                }
             }
         }
+        "sh" | "zsh" => print!("{}", format_sh(&env_vars)),
+        "fish" => print!("{}", format_fish(&env_vars)),
+        "powershell" | "pwsh" => print!("{}", format_powershell(&env_vars)),
+        "nu" | "nushell" => print!("{}", format_nu(&env_vars)),
         "json" => {
+            let value = match &options.nest {
+                Some(nest) => nest_env_vars(&env_vars, &nest.separator, nest.infer_types)?,
+                None => env_vars
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect::<serde_json::Map<String, serde_json::Value>>()
+                    .into(),
+            };
             let json =
-                serde_json::to_string_pretty(&env_vars).context("Failed to serialize to JSON")?;
+                serde_json::to_string_pretty(&value).context("Failed to serialize to JSON")?;
             println!("{json}");
         }
+        "yaml" => print!("{}", format_yaml(&env_vars)),
+        "toml" => print!("{}", format_toml(&env_vars)),
+        "docker" => print!("{}", format_docker(&env_vars)?),
+        "compose" => print!("{}", format_compose(&env_vars)),
+        "k8s-configmap" | "k8s-secret" | "k8s" => {
+            let metadata = manifest_metadata(file_path, &options)?;
+            // Without patterns each single-object format takes every key
+            let (config, secret) = if options.secret_keys.is_empty() && format != "k8s" {
+                (env_vars.clone(), env_vars)
+            } else {
+                partition_by_patterns(env_vars, &options.secret_keys)
+            };
+
+            match format.as_str() {
+                "k8s-configmap" => print!("{}", format_k8s_configmap(&config, &metadata)),
+                "k8s-secret" => print!("{}", format_k8s_secret(&secret, &metadata)),
+                _ => print!(
+                    "{}---\n{}",
+                    format_k8s_configmap(&config, &metadata),
+                    format_k8s_secret(&secret, &metadata)
+                ),
+            }
+        }
+        "systemd" => print!("{}", format_systemd(&env_vars)),
+        "systemd-dropin" => print!("{}", format_systemd_dropin(&env_vars)),
         _ => {
-            anyhow::bail!("Unsupported format: {}. Use 'bash' or 'json'", format);
+            anyhow::bail!(
+                "Unsupported format: {}. Use 'bash', 'sh', 'zsh', 'fish', 'powershell', 'nu', \
+                 'json', 'yaml', 'toml', 'docker', 'compose', 'k8s-configmap', 'k8s-secret', \
+                 'k8s', 'systemd' or 'systemd-dropin'",
+                format
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn manifest_metadata(file_path: &Path, options: &ExportOptions) -> Result<ManifestMetadata> {
+    let name = match &options.name {
+        Some(name) => name.clone(),
+        None => ManifestMetadata::name_from_file(
+            &file_path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default(),
+        ),
+    };
+    ManifestMetadata::new(&name, options.namespace.as_deref())
+}
+
+struct RunOptions {
+    no_override: bool,
+    clean: bool,
+    expand: bool,
+    process_env: bool,
+}
+
+fn run_command(
+    file_path: &PathBuf,
+    extra_files: &[PathBuf],
+    policy: DuplicatePolicy,
+    key_file: Option<&Path>,
+    options: RunOptions,
+    command: &[String],
+) -> Result<()> {
+    let (program, args) = command.split_first().context("No command given to run")?;
+
+    // Later files are appended so their definitions win, and so that references
+    // can span files when expanding
+    let mut lines = read_decrypted_env_file(file_path, key_file)?;
+    for extra_file in extra_files {
+        lines.extend(read_decrypted_env_file(extra_file, key_file)?);
+    }
+
+    let env_vars = if options.expand {
+        expand_env_vars(&lines, options.process_env, policy)?
+    } else {
+        get_env_vars_with_policy(&lines, policy)?
+    };
+
+    let mut child = ProcessCommand::new(program);
+    child.args(args);
+    if options.clean {
+        child.env_clear();
+    }
+    for (key, value) in env_vars {
+        if options.no_override && env::var_os(&key).is_some() {
+            continue;
+        }
+        child.env(key, value);
+    }
+
+    exec(child, program)
+}
+
+/// Replace this process with the child so its exit status and any signals it
+/// receives are seen directly by our caller
+#[cfg(unix)]
+fn exec(mut child: ProcessCommand, program: &str) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    let err = child.exec();
+    Err(err).with_context(|| format!("Failed to execute command: {program}"))
+}
+
+#[cfg(not(unix))]
+fn exec(mut child: ProcessCommand, program: &str) -> Result<()> {
+    let status = child
+        .status()
+        .with_context(|| format!("Failed to execute command: {program}"))?;
+    std::process::exit(status.code().unwrap_or(1))
+}
+
+/// Exit status used by `get` when a requested key is not set
+const EXIT_KEY_MISSING: i32 = 3;
+
+fn get_command(
+    file_path: &PathBuf,
+    policy: DuplicatePolicy,
+    key_file: Option<&Path>,
+    keys: &[String],
+    default: Option<&str>,
+    json: bool,
+    shell: bool,
+) -> Result<()> {
+    let lines = read_decrypted_env_file(file_path, key_file)?;
+    let env_vars = get_env_vars_with_policy(&lines, policy)?;
+
+    let mut values = Vec::new();
+    let mut missing = Vec::new();
+    for key in keys {
+        match env_vars.get(key).map(String::as_str).or(default) {
+            Some(value) => values.push((key.as_str(), value)),
+            None => missing.push(key.as_str()),
+        }
+    }
+
+    if !missing.is_empty() {
+        eprintln!(
+            "Key(s) not found in {}: {}",
+            file_path.display(),
+            missing.join(", ")
+        );
+        std::process::exit(EXIT_KEY_MISSING);
+    }
+
+    if json {
+        let map: serde_json::Map<String, serde_json::Value> = values
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.into()))
+            .collect();
+        let json = serde_json::to_string_pretty(&map).context("Failed to serialize to JSON")?;
+        println!("{json}");
+    } else {
+        for (_, value) in values {
+            if shell {
+                println!("{}", shell_escape(value));
+            } else {
+                println!("{value}");
+            }
         }
     }
 
@@ -77,46 +510,457 @@ fn keys_command(file_path: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn randomize_command(
+fn check_command(file_path: &PathBuf, policy: DuplicatePolicy) -> Result<()> {
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+    let (lines, mut diagnostics) = parse_env_file_with_diagnostics(&content);
+
+    let duplicate_severity = if policy == DuplicatePolicy::Error {
+        Severity::Error
+    } else {
+        Severity::Warning
+    };
+    diagnostics.extend(duplicate_key_diagnostics(&lines, duplicate_severity));
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+
+    for diagnostic in &diagnostics {
+        println!("{}:{diagnostic}", file_path.display());
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        anyhow::bail!("Found {errors} error(s) in {}", file_path.display());
+    }
+
+    Ok(())
+}
+
+/// Schema file `validate` uses when none is given, if it exists
+const DEFAULT_SCHEMA: &str = ".env.schema";
+
+fn validate_command(
     file_path: &PathBuf,
-    keys: Vec<String>,
+    policy: DuplicatePolicy,
+    key_file: Option<&Path>,
+    schema_path: Option<&Path>,
+) -> Result<()> {
+    let default_schema = sidecar_path(file_path, DEFAULT_SCHEMA);
+    let schema_path = schema_path.or_else(|| default_schema.exists().then_some(&*default_schema));
+    let mut schema = match schema_path {
+        Some(path) => Schema::load(path)?,
+        None => Schema::default(),
+    };
+
+    if !file_path.exists() {
+        anyhow::bail!("File not found: {}", file_path.display());
+    }
+    let lines = read_decrypted_env_file(file_path, key_file)?;
+    let (annotated, mut diagnostics) = annotated_schema(&lines);
+    schema.keys.extend(annotated.keys);
+    if schema.keys.is_empty() && diagnostics.is_empty() {
+        anyhow::bail!(
+            "Nothing to validate against: create {}, pass --schema, or annotate \
+             keys with comments such as '# @type int'",
+            default_schema.display()
+        );
+    }
+
+    diagnostics.extend(
+        validate_env_vars(&lines, &schema, policy)
+            .with_context(|| format!("Failed to validate {}", file_path.display()))?,
+    );
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+
+    for diagnostic in &diagnostics {
+        if diagnostic.line == 0 {
+            println!(
+                "{}: {}: {}",
+                file_path.display(),
+                diagnostic.severity,
+                diagnostic.message
+            );
+        } else {
+            println!("{}:{diagnostic}", file_path.display());
+        }
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        anyhow::bail!("Found {errors} violation(s) in {}", file_path.display());
+    }
+
+    Ok(())
+}
+
+enum ImportSource {
+    File {
+        path: PathBuf,
+        format: Option<String>,
+        flatten: FlattenOptions,
+    },
+    ProcessEnv {
+        prefix: String,
+        strip_prefix: bool,
+    },
+}
+
+fn import_command(
+    file_path: &PathBuf,
+    lock_timeout: Duration,
+    policy: DuplicatePolicy,
+    key_file: Option<&Path>,
+    source: ImportSource,
+    mode: MergeMode,
+) -> Result<()> {
+    let imported = match source {
+        ImportSource::ProcessEnv {
+            prefix,
+            strip_prefix,
+        } => process_env_vars(&prefix, strip_prefix)?,
+        ImportSource::File {
+            path,
+            format,
+            flatten,
+        } => {
+            let is_stdin = path.as_os_str() == "-";
+            let content = if is_stdin {
+                io::read_to_string(io::stdin()).context("Failed to read standard input")?
+            } else {
+                fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read file: {}", path.display()))?
+            };
+            let format =
+                format.unwrap_or_else(|| match path.extension().and_then(|ext| ext.to_str()) {
+                    Some(ext) if !is_stdin && ["json", "yaml", "yml"].contains(&ext) => {
+                        ext.to_string()
+                    }
+                    _ => "env".to_string(),
+                });
+            parse_import(&content, &format, &flatten)
+                .with_context(|| format!("Failed to import {}", path.display()))?
+        }
+    };
+
+    let _lock = lock_env_file(file_path, lock_timeout)?;
+    let mut lines = read_env_file(file_path)?;
+    let encryption_key = find_encryption_key(file_path, key_file, &lines)?;
+    let (summary, pairs) = plan_import(&lines, imported, mode, policy, encryption_key.as_ref())?;
+    let pairs = encrypt_replacements(file_path, encryption_key.as_ref(), &lines, pairs, policy)?;
+    set_env_vars_with_policy(&mut lines, pairs, policy)?;
+
+    if summary.has_changes() {
+        save_env_file(file_path, &lines)?;
+    }
+
+    for (label, keys) in [
+        ("added", &summary.added),
+        ("changed", &summary.changed),
+        ("unchanged", &summary.unchanged),
+        ("kept existing", &summary.kept),
+    ] {
+        if !keys.is_empty() {
+            println!("{label}: {}", keys.join(", "));
+        }
+    }
+    println!(
+        "{} added, {} changed, {} unchanged, {} kept existing",
+        summary.added.len(),
+        summary.changed.len(),
+        summary.unchanged.len(),
+        summary.kept.len()
+    );
+
+    Ok(())
+}
+
+/// Read an env file with encrypted values decrypted when a key is available. Without a
+/// key the encrypted values are passed through unchanged, with a warning.
+fn read_decrypted_env_file(file_path: &PathBuf, key_file: Option<&Path>) -> Result<Vec<EnvLine>> {
+    let mut lines = read_env_file(file_path)?;
+    let encrypted = lines
+        .iter()
+        .filter(|line| matches!(line, EnvLine::KeyValue { value, .. } if is_encrypted(value)))
+        .count();
+    if encrypted == 0 {
+        return Ok(lines);
+    }
+
+    match EncryptionKey::find(file_path, key_file)? {
+        Some(key) => decrypt_all_values(&mut lines, &key)
+            .with_context(|| format!("Failed to decrypt {}", file_path.display()))?,
+        None => eprintln!(
+            "warning: {encrypted} encrypted value(s) in {} left as-is: no key found \
+             (use --key-file, set {KEY_ENV_VAR} or create {})",
+            file_path.display(),
+            key_file_path(file_path).display()
+        ),
+    }
+    Ok(lines)
+}
+
+fn encrypt_command(
+    file_path: &PathBuf,
+    lock_timeout: Duration,
+    policy: DuplicatePolicy,
+    key_file: Option<&Path>,
+    keys: &[String],
+) -> Result<()> {
+    let _lock = lock_env_file(file_path, lock_timeout)?;
+    let mut lines = read_env_file(file_path)?;
+
+    let keys = if keys.is_empty() {
+        let (schema, _) = annotated_schema(&lines);
+        let mut secrets = Vec::new();
+        for key in get_env_keys(&lines) {
+            if schema.keys.get(&key).is_some_and(|spec| spec.secret) && !secrets.contains(&key) {
+                secrets.push(key);
+            }
+        }
+        if secrets.is_empty() {
+            anyhow::bail!("No keys given and none are annotated '# @secret'");
+        }
+        secrets
+    } else {
+        keys.to_vec()
+    };
+
+    // An explicit key file that does not exist yet is created rather than an error
+    let existing = match key_file {
+        Some(path) if !path.exists() => None,
+        _ => EncryptionKey::find(file_path, key_file)?,
+    };
+    let (encryption_key, new_key_path) = match existing {
+        Some(key) => (key, None),
+        None => {
+            let path = key_file.map_or_else(|| key_file_path(file_path), Path::to_path_buf);
+            (EncryptionKey::generate(), Some(path))
+        }
+    };
+
+    // Missing keys fail here, before a new key file is written
+    let encrypted = encrypt_env_vars(&mut lines, &keys, &encryption_key, policy)?;
+    if !encrypted.is_empty() {
+        if let Some(path) = new_key_path {
+            encryption_key.save(&path)?;
+            println!(
+                "Created key file {}; keep it out of version control",
+                path.display()
+            );
+        }
+        save_env_file(file_path, &lines)?;
+        println!("encrypted: {}", encrypted.join(", "));
+    }
+    let skipped: Vec<&str> = keys
+        .iter()
+        .filter(|key| !encrypted.contains(key))
+        .map(String::as_str)
+        .collect();
+    if !skipped.is_empty() {
+        println!("already encrypted: {}", skipped.join(", "));
+    }
+
+    Ok(())
+}
+
+fn decrypt_command(
+    file_path: &PathBuf,
+    lock_timeout: Duration,
+    policy: DuplicatePolicy,
+    key_file: Option<&Path>,
+    keys: &[String],
+) -> Result<()> {
+    let encryption_key = EncryptionKey::find(file_path, key_file)?.with_context(|| {
+        format!(
+            "No key found: use --key-file, set {KEY_ENV_VAR} or create {}",
+            key_file_path(file_path).display()
+        )
+    })?;
+
+    let _lock = lock_env_file(file_path, lock_timeout)?;
+    let mut lines = read_env_file(file_path)?;
+
+    let keys = if keys.is_empty() {
+        let mut encrypted = Vec::new();
+        for line in &lines {
+            if let EnvLine::KeyValue { key, value, .. } = line {
+                if is_encrypted(value) && !encrypted.contains(key) {
+                    encrypted.push(key.clone());
+                }
+            }
+        }
+        encrypted
+    } else {
+        keys.to_vec()
+    };
+
+    let decrypted = decrypt_env_vars(&mut lines, &keys, &encryption_key, policy)?;
+    if decrypted.is_empty() {
+        println!("No encrypted values to decrypt in {}", file_path.display());
+        return Ok(());
+    }
+
+    save_env_file(file_path, &lines)?;
+    println!("decrypted: {}", decrypted.join(", "));
+
+    Ok(())
+}
+
+fn dedupe_command(
+    file_path: &PathBuf,
+    lock_timeout: Duration,
+    policy: DuplicatePolicy,
+) -> Result<()> {
+    let _lock = lock_env_file(file_path, lock_timeout)?;
+    let mut lines = read_env_file(file_path)?;
+    let removed = dedupe_env_vars(&mut lines, policy)
+        .with_context(|| format!("Failed to dedupe {}", file_path.display()))?;
+
+    if removed.is_empty() {
+        println!("No duplicate keys in {}", file_path.display());
+        return Ok(());
+    }
+
+    save_env_file(file_path, &lines)?;
+    for (key, count) in removed {
+        println!("{key}: removed {count} duplicate definition(s)");
+    }
+
+    Ok(())
+}
+
+fn sync_command(
+    file_path: &PathBuf,
+    lock_timeout: Duration,
+    policy: DuplicatePolicy,
+    template_path: &Path,
+) -> Result<()> {
+    let content = fs::read_to_string(template_path)
+        .with_context(|| format!("Failed to read template: {}", template_path.display()))?;
+    let template = parse_env_file(&content);
+
+    let _lock = lock_env_file(file_path, lock_timeout)?;
+    let mut lines = read_env_file(file_path)?;
+    let summary = sync_env_vars(&mut lines, &template, policy)
+        .with_context(|| format!("Failed to sync {}", file_path.display()))?;
+
+    if !summary.added.is_empty() {
+        save_env_file(file_path, &lines)?;
+    }
+
+    for (label, keys) in [
+        ("added", &summary.added),
+        ("not in template", &summary.not_in_template),
+    ] {
+        if !keys.is_empty() {
+            println!("{label}: {}", keys.join(", "));
+        }
+    }
+    println!(
+        "{} added, {} not in template",
+        summary.added.len(),
+        summary.not_in_template.len()
+    );
+
+    Ok(())
+}
+
+fn example_command(
+    file_path: &PathBuf,
+    output: Option<&PathBuf>,
+    placeholder: PlaceholderStyle,
+) -> Result<()> {
+    if !file_path.exists() {
+        anyhow::bail!("File not found: {}", file_path.display());
+    }
+    let lines = read_env_file(file_path)?;
+    let example = example_env_lines(&lines, placeholder);
+
+    match output {
+        Some(output) => save_env_file(output, &example)?,
+        None => print!("{}", write_env_file(&example)),
+    }
+
+    Ok(())
+}
+
+/// Exit status used by `diff` when the files differ
+const EXIT_FILES_DIFFER: i32 = 1;
+
+/// Exit status used by `diff` when a file cannot be compared, as with diff(1)
+const EXIT_DIFF_TROUBLE: i32 = 2;
+
+fn diff_command(
+    a: &PathBuf,
+    b: &PathBuf,
+    policy: DuplicatePolicy,
+    show_values: bool,
+    keys_only: bool,
+) -> Result<()> {
+    let read = |path: &PathBuf| -> Result<OrderedEnvVars> {
+        // read_env_file treats a missing file as empty, which would hide a typo here
+        if !path.exists() {
+            anyhow::bail!("File not found: {}", path.display());
+        }
+        let lines = read_env_file(path)?;
+        get_ordered_env_vars(&lines, policy, KeyOrder::File)
+            .with_context(|| format!("Failed to read variables from {}", path.display()))
+    };
+    let diffs = match read(a).and_then(|a| Ok(diff_env_vars(&a, &read(b)?))) {
+        Ok(diffs) => diffs,
+        Err(e) => {
+            // Report the error as `main` would, but with a status distinct from "files differ"
+            eprintln!("Error: {e:?}");
+            std::process::exit(EXIT_DIFF_TROUBLE);
+        }
+    };
+
+    if diffs.is_empty() {
+        return Ok(());
+    }
+
+    for diff in &diffs {
+        println!("{}", diff.render(show_values, keys_only));
+    }
+    std::process::exit(EXIT_FILES_DIFFER);
+}
+
+struct RandomizeOptions {
     numeric: bool,
     symbol: bool,
     length: usize,
     xkcd: bool,
+}
+
+fn randomize_command(
+    file_path: &PathBuf,
+    lock_timeout: Duration,
+    policy: DuplicatePolicy,
+    key_file: Option<&Path>,
+    options: RandomizeOptions,
+    keys: Vec<String>,
 ) -> Result<()> {
+    let _lock = lock_env_file(file_path, lock_timeout)?;
     let mut lines = read_env_file(file_path)?;
 
+    let mut pairs = Vec::new();
     for key in keys {
-        let password = if xkcd {
+        let password = if options.xkcd {
             generate_xkcd_password()?
         } else {
-            generate_random_password(length, numeric, symbol)
+            generate_random_password(options.length, options.numeric, options.symbol)
         };
-
-        let mut found = false;
-        for line in &mut lines {
-            if let EnvLine::KeyValue {
-                key: existing_key,
-                value: existing_value,
-            } = line
-            {
-                if existing_key == &key {
-                    *existing_value = password.clone();
-                    found = true;
-                    break;
-                }
-            }
-        }
-
-        if !found {
-            lines.push(EnvLine::KeyValue {
-                key,
-                value: password,
-            });
-        }
+        pairs.push(format!("{key}={password}"));
     }
 
+    let encryption_key = find_encryption_key(file_path, key_file, &lines)?;
+    let pairs = encrypt_replacements(file_path, encryption_key.as_ref(), &lines, pairs, policy)?;
+    set_env_vars_with_policy(&mut lines, pairs, policy)?;
     save_env_file(file_path, &lines)
 }
 
@@ -0,0 +1,320 @@
+// Mapping between nested configuration and flat variable names.
+//
+// Nested objects and arrays flatten into keys joined by a separator, `__` by default:
+// `{"db": {"hosts": ["a", "b"]}}` becomes `db__hosts__0=a` and `db__hosts__1=b`.
+// Nesting reverses this, turning runs of numeric segments `0..n` back into arrays and,
+// optionally, values that read back exactly as booleans or numbers into those types.
+
+use crate::OrderedEnvVars;
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+/// How nested values are turned into flat keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlattenOptions {
+    /// Joins the segments of a nested path
+    pub separator: String,
+    /// Upper-case every segment, so `db.host` becomes `DB__HOST`
+    pub uppercase: bool,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            separator: "__".to_string(),
+            uppercase: false,
+        }
+    }
+}
+
+/// Flatten a JSON object into key/value pairs in document order. Strings are kept as-is,
+/// numbers and booleans become their text and null becomes an empty value.
+pub fn flatten_value(value: &Value, options: &FlattenOptions) -> Result<OrderedEnvVars> {
+    let Value::Object(object) = value else {
+        anyhow::bail!("Expected an object of variables");
+    };
+
+    let mut vars = Vec::new();
+    for (key, value) in object {
+        flatten_into(&segment(key, options), value, options, &mut vars);
+    }
+    Ok(vars)
+}
+
+fn flatten_into(path: &str, value: &Value, options: &FlattenOptions, vars: &mut OrderedEnvVars) {
+    let child = |key: &str| format!("{path}{}{}", options.separator, segment(key, options));
+
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                flatten_into(&child(key), value, options, vars);
+            }
+        }
+        Value::Array(items) => {
+            for (idx, value) in items.iter().enumerate() {
+                flatten_into(&child(&idx.to_string()), value, options, vars);
+            }
+        }
+        Value::String(s) => vars.push((path.to_string(), s.clone())),
+        Value::Null => vars.push((path.to_string(), String::new())),
+        Value::Bool(_) | Value::Number(_) => vars.push((path.to_string(), value.to_string())),
+    }
+}
+
+fn segment(key: &str, options: &FlattenOptions) -> String {
+    if options.uppercase {
+        key.to_uppercase()
+    } else {
+        key.to_string()
+    }
+}
+
+/// Rebuild a nested JSON object from flat keys split on `separator`. Keys with an empty
+/// segment (such as `__PRIVATE`) are kept whole. A key that is both a value and the
+/// parent of other keys is an error.
+pub fn nest_env_vars(
+    env_vars: &[(String, String)],
+    separator: &str,
+    infer_types: bool,
+) -> Result<Value> {
+    if separator.is_empty() {
+        anyhow::bail!("Separator must not be empty");
+    }
+
+    let mut root = Node::Branch(Vec::new());
+    for (key, value) in env_vars {
+        let segments: Vec<&str> = match key.split(separator).collect::<Vec<_>>() {
+            segments if segments.iter().any(|s| s.is_empty()) => vec![key.as_str()],
+            segments => segments,
+        };
+        root.insert(key, &segments, value)?;
+    }
+
+    Ok(root.into_value(infer_types))
+}
+
+enum Node {
+    Leaf(String),
+    Branch(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn insert(&mut self, key: &str, segments: &[&str], value: &str) -> Result<()> {
+        let Node::Branch(children) = self else {
+            unreachable!("values are only inserted below branches");
+        };
+        let (first, rest) = segments.split_first().expect("keys have a segment");
+
+        let existing = children.iter_mut().find(|(name, _)| name == first);
+        match (existing, rest.is_empty()) {
+            (None, true) => children.push((first.to_string(), Node::Leaf(value.to_string()))),
+            (None, false) => {
+                let mut branch = Node::Branch(Vec::new());
+                branch.insert(key, rest, value)?;
+                children.push((first.to_string(), branch));
+            }
+            (Some((_, node @ Node::Branch(_))), false) => node.insert(key, rest, value)?,
+            (Some(_), _) => {
+                anyhow::bail!("Cannot nest {key}: {first} is both a value and a group of keys")
+            }
+        }
+        Ok(())
+    }
+
+    fn into_value(self, infer_types: bool) -> Value {
+        match self {
+            Node::Leaf(value) if infer_types => infer_type(value),
+            Node::Leaf(value) => Value::String(value),
+            Node::Branch(children) if is_array(&children) => {
+                let mut items: Vec<(usize, Node)> = children
+                    .into_iter()
+                    .map(|(name, node)| (name.parse().expect("checked numeric"), node))
+                    .collect();
+                items.sort_by_key(|(idx, _)| *idx);
+                Value::Array(
+                    items
+                        .into_iter()
+                        .map(|(_, node)| node.into_value(infer_types))
+                        .collect(),
+                )
+            }
+            Node::Branch(children) => Value::Object(
+                children
+                    .into_iter()
+                    .map(|(name, node)| (name, node.into_value(infer_types)))
+                    .collect::<Map<String, Value>>(),
+            ),
+        }
+    }
+}
+
+/// Whether the children are exactly the indices `0..n`, in any order
+fn is_array(children: &[(String, Node)]) -> bool {
+    let mut indices: Vec<usize> = Vec::with_capacity(children.len());
+    for (name, _) in children {
+        // Reject forms like `01` or `+1` that would not flatten back to the same key
+        match name.parse::<usize>() {
+            Ok(idx) if idx.to_string() == *name => indices.push(idx),
+            _ => return false,
+        }
+    }
+    indices.sort_unstable();
+    !indices.is_empty() && indices.iter().enumerate().all(|(i, &idx)| i == idx)
+}
+
+/// Turn a value into a boolean or number when it reads back as exactly the same text,
+/// so `8080` becomes a number but `08080` and `1.50` stay strings
+fn infer_type(value: String) -> Value {
+    match value.as_str() {
+        "true" => return Value::Bool(true),
+        "false" => return Value::Bool(false),
+        _ => {}
+    }
+
+    match serde_json::from_str::<Value>(&value) {
+        Ok(Value::Number(number)) if number.to_string() == value => Value::Number(number),
+        _ => Value::String(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn vars(pairs: &[(&str, &str)]) -> OrderedEnvVars {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    // ==================== FLATTEN TESTS ====================
+
+    #[test]
+    fn test_flatten_nested_objects_and_arrays() {
+        let value = json!({
+            "db": {"host": "localhost", "port": 5432, "replicas": ["a", "b"]},
+            "debug": true,
+            "empty": null,
+        });
+        assert_eq!(
+            flatten_value(&value, &FlattenOptions::default()).unwrap(),
+            vars(&[
+                ("db__host", "localhost"),
+                ("db__port", "5432"),
+                ("db__replicas__0", "a"),
+                ("db__replicas__1", "b"),
+                ("debug", "true"),
+                ("empty", ""),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_flatten_uppercase_and_custom_separator() {
+        let options = FlattenOptions {
+            separator: "_".to_string(),
+            uppercase: true,
+        };
+        let value = json!({"db": {"host": "x"}, "list": [{"name": "n"}]});
+        assert_eq!(
+            flatten_value(&value, &options).unwrap(),
+            vars(&[("DB_HOST", "x"), ("LIST_0_NAME", "n")])
+        );
+        assert!(flatten_value(&json!(["not", "an", "object"]), &options).is_err());
+    }
+
+    // ==================== NEST TESTS ====================
+
+    #[test]
+    fn test_nest_rebuilds_structure() {
+        let nested = nest_env_vars(
+            &vars(&[
+                ("DB__HOST", "localhost"),
+                ("DB__PORT", "5432"),
+                ("DB__REPLICAS__1", "b"),
+                ("DB__REPLICAS__0", "a"),
+                ("DEBUG", "true"),
+            ]),
+            "__",
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            nested,
+            json!({
+                "DB": {"HOST": "localhost", "PORT": "5432", "REPLICAS": ["a", "b"]},
+                "DEBUG": "true",
+            })
+        );
+    }
+
+    #[test]
+    fn test_nest_infers_types_only_when_lossless() {
+        let nested = nest_env_vars(
+            &vars(&[
+                ("PORT", "8080"),
+                ("RATE", "0.5"),
+                ("NEG", "-3"),
+                ("ON", "true"),
+                ("ZIP", "08080"),
+                ("PRICE", "1.50"),
+                ("YES", "yes"),
+                ("BIG", "1e3"),
+            ]),
+            "__",
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            nested,
+            json!({
+                "PORT": 8080, "RATE": 0.5, "NEG": -3, "ON": true,
+                "ZIP": "08080", "PRICE": "1.50", "YES": "yes", "BIG": "1e3",
+            })
+        );
+    }
+
+    #[test]
+    fn test_nest_non_contiguous_indices_stay_objects() {
+        let nested = nest_env_vars(
+            &vars(&[("L__0", "a"), ("L__2", "c"), ("M__01", "x")]),
+            "__",
+            false,
+        )
+        .unwrap();
+        assert_eq!(nested, json!({"L": {"0": "a", "2": "c"}, "M": {"01": "x"}}));
+    }
+
+    #[test]
+    fn test_nest_keeps_keys_with_empty_segments_whole() {
+        let nested =
+            nest_env_vars(&vars(&[("__PRIVATE", "1"), ("A__", "2")]), "__", false).unwrap();
+        assert_eq!(nested, json!({"__PRIVATE": "1", "A__": "2"}));
+    }
+
+    #[test]
+    fn test_nest_conflicts() {
+        let err = nest_env_vars(&vars(&[("DB", "x"), ("DB__HOST", "y")]), "__", false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot nest DB__HOST: DB is both a value and a group of keys"
+        );
+        assert!(nest_env_vars(&vars(&[("DB__HOST", "y"), ("DB", "x")]), "__", false).is_err());
+        assert!(nest_env_vars(&[], "", false).is_err());
+    }
+
+    #[test]
+    fn test_flatten_then_nest_round_trip() {
+        let value = json!({
+            "db": {"host": "h", "ports": [1, 2], "tls": {"enabled": false}},
+            "name": "app",
+        });
+        let flat = flatten_value(&value, &FlattenOptions::default()).unwrap();
+        assert_eq!(nest_env_vars(&flat, "__", true).unwrap(), value);
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.
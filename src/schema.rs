@@ -0,0 +1,474 @@
+// Declaring what an env file must contain and checking a file against it.
+//
+// A schema is a TOML (or JSON) table with one entry per key:
+//
+//     [PORT]
+//     type = "int"
+//     required = true
+//
+//     [LOG_LEVEL]
+//     type = "enum"
+//     values = ["debug", "info", "warn", "error"]
+//     default = "info"
+//
+//     [API_TOKEN]
+//     pattern = "tok_[a-z0-9]{32}"
+//     secret = true
+//
+// Types are string (the default), int, bool, url, enum and duration. Patterns must match
+// the whole value. Values of secret keys are never repeated in validation messages.
+// Encrypted values that could not be decrypted are only checked for being present.
+
+use crate::example::is_duration;
+use crate::{
+    effective_definitions, is_encrypted, key_column, line_numbers, Diagnostic, DuplicatePolicy,
+    EnvLine,
+};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The kind of value a key holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    #[default]
+    String,
+    /// A signed 64-bit integer
+    Int,
+    /// `true`, `false`, `1` or `0`
+    Bool,
+    /// An absolute URL such as `https://example.com` or `postgres://db/app`
+    Url,
+    /// One of the spec's `values`
+    Enum,
+    /// Numbers with units such as `500ms`, `30s` or `1h30m`
+    Duration,
+}
+
+/// What the schema declares about one key
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeySpec {
+    #[serde(rename = "type", default)]
+    pub value_type: ValueType,
+    /// The key must be present with a non-empty value
+    #[serde(default)]
+    pub required: bool,
+    /// Value the application uses when the key is not set
+    #[serde(default, deserialize_with = "deserialize_scalar")]
+    pub default: Option<String>,
+    /// Regular expression the whole value must match
+    #[serde(default, deserialize_with = "deserialize_pattern")]
+    pub pattern: Option<Regex>,
+    /// Allowed values for `enum` keys
+    #[serde(default)]
+    pub values: Vec<String>,
+    /// The value is sensitive and must not be displayed
+    #[serde(default)]
+    pub secret: bool,
+    /// The value is safe to publish, so generated examples keep it
+    #[serde(default)]
+    pub public: bool,
+}
+
+impl KeySpec {
+    /// Why the spec contradicts itself, if it does
+    pub(crate) fn inconsistency(&self, key: &str) -> Option<String> {
+        match (self.value_type, self.values.is_empty()) {
+            (ValueType::Enum, true) => {
+                return Some(format!("'{key}' has type enum but lists no values"))
+            }
+            (ValueType::Enum, false) | (_, true) => {}
+            (_, false) => return Some(format!("'{key}' lists values but does not have type enum")),
+        }
+        if self.secret && self.public {
+            return Some(format!("'{key}' cannot be both secret and public"));
+        }
+        let default = self.default.as_deref()?;
+        self.problems(key, default)
+            .into_iter()
+            .next()
+            .map(|problem| format!("invalid default: {problem}"))
+    }
+
+    /// Every way `value` breaks the spec, as messages about `key`
+    pub fn problems(&self, key: &str, value: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        let shown = if self.secret {
+            String::new()
+        } else {
+            format!(" (got {value:?})")
+        };
+
+        if !self.value_type.accepts(value, &self.values) {
+            let expected = match self.value_type {
+                ValueType::String => unreachable!("every value is a string"),
+                ValueType::Int => "an integer".to_string(),
+                ValueType::Bool => "a boolean (true, false, 1 or 0)".to_string(),
+                ValueType::Url => "a URL".to_string(),
+                ValueType::Enum => format!("one of {}", self.values.join(", ")),
+                ValueType::Duration => "a duration such as 30s or 1h30m".to_string(),
+            };
+            problems.push(format!("'{key}' must be {expected}{shown}"));
+        }
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(value) {
+                problems.push(format!(
+                    "'{key}' does not match the pattern {}{shown}",
+                    display_pattern(pattern)
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+impl ValueType {
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueType::String => "string",
+            ValueType::Int => "int",
+            ValueType::Bool => "bool",
+            ValueType::Url => "url",
+            ValueType::Enum => "enum",
+            ValueType::Duration => "duration",
+        }
+    }
+
+    fn accepts(self, value: &str, values: &[String]) -> bool {
+        match self {
+            ValueType::String => true,
+            ValueType::Int => value.parse::<i64>().is_ok(),
+            ValueType::Bool => matches!(value, "true" | "false" | "1" | "0"),
+            ValueType::Url => is_url(value),
+            ValueType::Enum => values.iter().any(|allowed| allowed == value),
+            ValueType::Duration => is_duration(value),
+        }
+    }
+}
+
+impl FromStr for ValueType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "string" => Ok(ValueType::String),
+            "int" => Ok(ValueType::Int),
+            "bool" => Ok(ValueType::Bool),
+            "url" => Ok(ValueType::Url),
+            "enum" => Ok(ValueType::Enum),
+            "duration" => Ok(ValueType::Duration),
+            _ => Err(format!(
+                "unknown type '{s}' (expected string, int, bool, url, enum or duration)"
+            )),
+        }
+    }
+}
+
+/// A scheme, `://` and a non-empty remainder without whitespace
+fn is_url(value: &str) -> bool {
+    let Some((scheme, rest)) = value.split_once("://") else {
+        return false;
+    };
+    let mut scheme_chars = scheme.chars();
+    scheme_chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic())
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !rest.is_empty()
+        && !rest.chars().any(char::is_whitespace)
+}
+
+/// Accept strings, numbers and booleans, so TOML can say `default = 8080`
+fn deserialize_scalar<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<String>, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(Some(s)),
+        value @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_)) => {
+            Ok(Some(value.to_string()))
+        }
+        _ => Err(D::Error::custom("expected a string, number or boolean")),
+    }
+}
+
+fn deserialize_pattern<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Regex>, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    compile_pattern(&pattern)
+        .map(Some)
+        .map_err(D::Error::custom)
+}
+
+/// Compile a pattern anchored at both ends so it has to match the whole value
+pub(crate) fn compile_pattern(pattern: &str) -> std::result::Result<Regex, String> {
+    Regex::new(&format!("^(?:{pattern})$")).map_err(|e| format!("invalid pattern '{pattern}': {e}"))
+}
+
+/// The pattern as written in the schema, without the anchors added when compiling it
+fn display_pattern(pattern: &Regex) -> &str {
+    let anchored = pattern.as_str();
+    &anchored["^(?:".len()..anchored.len() - ")$".len()]
+}
+
+/// Per-key declarations, listed by key name
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    pub keys: BTreeMap<String, KeySpec>,
+}
+
+impl Schema {
+    /// Parse a schema written as JSON when `json` is set, TOML otherwise
+    pub fn parse(content: &str, json: bool) -> Result<Self> {
+        let keys: BTreeMap<String, KeySpec> = if json {
+            serde_json::from_str(content).context("Invalid JSON schema")?
+        } else {
+            toml::from_str(content).context("Invalid TOML schema")?
+        };
+
+        for (key, spec) in &keys {
+            if let Some(problem) = spec.inconsistency(key) {
+                anyhow::bail!("{problem}");
+            }
+        }
+
+        Ok(Self { keys })
+    }
+
+    /// Read a schema file, treating files ending in `.json` as JSON and everything else,
+    /// such as `.env.schema`, as TOML
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read schema: {}", path.display()))?;
+        let json = path.extension().is_some_and(|ext| ext == "json");
+        Self::parse(&content, json)
+            .with_context(|| format!("Failed to load schema: {}", path.display()))
+    }
+}
+
+/// Check the definitions `policy` selects against `schema`, returning an error for every
+/// violation at the line and column of the offending key. Missing required keys are not
+/// tied to a line and are reported on line 0, before everything else.
+pub fn validate_env_vars(
+    lines: &[EnvLine],
+    schema: &Schema,
+    policy: DuplicatePolicy,
+) -> Result<Vec<Diagnostic>> {
+    let definitions = effective_definitions(lines, policy)?;
+    let numbers = line_numbers(lines);
+    let mut diagnostics = Vec::new();
+
+    for (key, spec) in &schema.keys {
+        let Some(&idx) = definitions.get(key.as_str()) else {
+            if spec.required {
+                diagnostics.push(Diagnostic::error(
+                    0,
+                    0,
+                    format!("missing required key '{key}'"),
+                ));
+            }
+            continue;
+        };
+        let EnvLine::KeyValue { value, format, .. } = &lines[idx] else {
+            continue;
+        };
+        let (line, column) = (numbers[idx], key_column(key, format));
+
+        if value.is_empty() {
+            if spec.required {
+                diagnostics.push(Diagnostic::error(
+                    line,
+                    column,
+                    format!("required key '{key}' is empty"),
+                ));
+            }
+            continue;
+        }
+        // Without a key an encrypted value is opaque, so only its presence can be checked
+        if is_encrypted(value) {
+            continue;
+        }
+        for problem in spec.problems(key, value) {
+            diagnostics.push(Diagnostic::error(line, column, problem));
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_env_file;
+    use pretty_assertions::assert_eq;
+
+    const SCHEMA: &str = r#"
+[PORT]
+type = "int"
+required = true
+default = 8080
+
+[DEBUG]
+type = "bool"
+
+[DATABASE_URL]
+type = "url"
+required = true
+
+[LOG_LEVEL]
+type = "enum"
+values = ["debug", "info", "warn"]
+
+[TIMEOUT]
+type = "duration"
+
+[API_TOKEN]
+pattern = "tok_[a-z0-9]{4}"
+secret = true
+"#;
+
+    fn validate(content: &str) -> Vec<String> {
+        let schema = Schema::parse(SCHEMA, false).unwrap();
+        validate_env_vars(&parse_env_file(content), &schema, DuplicatePolicy::LastWins)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    // ==================== SCHEMA PARSING TESTS ====================
+
+    #[test]
+    fn test_parse_toml_schema() {
+        let schema = Schema::parse(SCHEMA, false).unwrap();
+        let port = &schema.keys["PORT"];
+        assert_eq!(port.value_type, ValueType::Int);
+        assert!(port.required);
+        assert_eq!(port.default.as_deref(), Some("8080"));
+        assert!(schema.keys["API_TOKEN"].secret);
+        assert_eq!(schema.keys["API_TOKEN"].value_type, ValueType::String);
+    }
+
+    #[test]
+    fn test_parse_json_schema() {
+        let schema = Schema::parse(
+            r#"{"MODE": {"type": "enum", "values": ["a", "b"], "default": "a"}}"#,
+            true,
+        )
+        .unwrap();
+        assert_eq!(schema.keys["MODE"].values, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_schemas() {
+        for (schema, message) in [
+            ("[A]\ntype = \"float\"\n", "Invalid TOML schema"),
+            ("[A]\nrequierd = true\n", "Invalid TOML schema"),
+            ("[A]\npattern = \"(\"\n", "Invalid TOML schema"),
+            ("[A]\ntype = \"enum\"\n", "lists no values"),
+            ("[A]\nvalues = [\"x\"]\n", "does not have type enum"),
+            (
+                "[A]\ntype = \"int\"\ndefault = \"abc\"\n",
+                "invalid default",
+            ),
+            (
+                "[A]\nsecret = true\npublic = true\n",
+                "both secret and public",
+            ),
+        ] {
+            let err = Schema::parse(schema, false).unwrap_err();
+            assert!(format!("{err:#}").contains(message), "{schema}: {err:#}");
+        }
+    }
+
+    // ==================== VALIDATION TESTS ====================
+
+    #[test]
+    fn test_validate_valid_file() {
+        let content = "PORT=8080\nDEBUG=true\nDATABASE_URL=postgres://db/app\nLOG_LEVEL=info\nTIMEOUT=30s\nAPI_TOKEN=tok_ab12\n";
+        assert!(validate(content).is_empty());
+    }
+
+    #[test]
+    fn test_validate_reports_every_violation_with_lines() {
+        let content = "\
+# comment
+PORT=abc
+DEBUG=yes
+  LOG_LEVEL=trace
+TIMEOUT=\"30
+seconds\"
+API_TOKEN=hunter2
+";
+        assert_eq!(
+            validate(content),
+            vec![
+                "0:0: error: missing required key 'DATABASE_URL'",
+                "2:1: error: 'PORT' must be an integer (got \"abc\")",
+                "3:1: error: 'DEBUG' must be a boolean (true, false, 1 or 0) (got \"yes\")",
+                "4:3: error: 'LOG_LEVEL' must be one of debug, info, warn (got \"trace\")",
+                "5:1: error: 'TIMEOUT' must be a duration such as 30s or 1h30m (got \"30\\nseconds\")",
+                "7:1: error: 'API_TOKEN' does not match the pattern tok_[a-z0-9]{4}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_empty_values() {
+        assert_eq!(
+            validate("PORT=\nDATABASE_URL=https://x\nDEBUG=\n"),
+            vec!["1:1: error: required key 'PORT' is empty"]
+        );
+    }
+
+    #[test]
+    fn test_validate_only_checks_presence_of_encrypted_values() {
+        assert_eq!(
+            validate("PORT=enc:v1:AAAA\nDATABASE_URL=enc:v1:BBBB\nLOG_LEVEL=enc:v1:CCCC\n"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_validate_uses_duplicate_policy() {
+        let schema = Schema::parse(SCHEMA, false).unwrap();
+        let lines = parse_env_file("PORT=abc\nPORT=1\nDATABASE_URL=https://x\n");
+        assert!(
+            validate_env_vars(&lines, &schema, DuplicatePolicy::LastWins)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            validate_env_vars(&lines, &schema, DuplicatePolicy::FirstWins)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(validate_env_vars(&lines, &schema, DuplicatePolicy::Error).is_err());
+    }
+
+    #[test]
+    fn test_is_url() {
+        for value in [
+            "https://example.com",
+            "postgres://u:p@h:5432/db",
+            "redis://h",
+        ] {
+            assert!(is_url(value), "{value}");
+        }
+        for value in ["example.com", "://x", "1http://x", "http://", "http://a b"] {
+            assert!(!is_url(value), "{value}");
+        }
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.
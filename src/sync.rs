@@ -0,0 +1,242 @@
+// Bringing an env file up to date with a template such as `.env.example`.
+//
+// Keys the template defines but the file lacks are copied over exactly as the template
+// writes them, together with the comment lines directly above them. Each one is placed
+// after the nearest earlier template key the file already has, so the file follows the
+// template's order; blank lines around the template entry are mirrored where the file has
+// none. Copied lines take the file's line ending. Existing values are never changed.
+
+use crate::{
+    effective_definitions, find_definition, get_env_keys, set_line_ending, uses_crlf,
+    DuplicatePolicy, EnvLine,
+};
+use anyhow::Result;
+use std::collections::HashSet;
+
+/// Keys a sync added to the file and keys the file has that the template lacks, both in
+/// file order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub added: Vec<String>,
+    pub not_in_template: Vec<String>,
+}
+
+/// Add every key from `template` that `lines` does not define, using the template
+/// definition `policy` selects for duplicated keys
+pub fn sync_env_vars(
+    lines: &mut Vec<EnvLine>,
+    template: &[EnvLine],
+    policy: DuplicatePolicy,
+) -> Result<SyncSummary> {
+    let definitions = effective_definitions(template, policy)?;
+    // Surface duplicates in the file itself before anything is inserted
+    effective_definitions(lines, policy)?;
+
+    let existing: HashSet<String> = get_env_keys(lines).into_iter().collect();
+    // An empty file has no line ending of its own, so it takes the template's
+    let crlf = if lines.is_empty() {
+        uses_crlf(template)
+    } else {
+        uses_crlf(lines)
+    };
+    let mut summary = SyncSummary::default();
+    for key in get_env_keys(lines) {
+        if !definitions.contains_key(key.as_str()) && !summary.not_in_template.contains(&key) {
+            summary.not_in_template.push(key);
+        }
+    }
+
+    // Keys missing before the first template key the file has go above its comments
+    let mut cursor = template
+        .iter()
+        .enumerate()
+        .find_map(|(idx, line)| match line {
+            EnvLine::KeyValue { key, .. } if definitions[key.as_str()] == idx => {
+                find_definition(lines, key, policy).ok().flatten()
+            }
+            _ => None,
+        })
+        .map_or(lines.len(), |idx| comment_block_start(lines, idx));
+
+    for (idx, line) in template.iter().enumerate() {
+        let EnvLine::KeyValue { key, .. } = line else {
+            continue;
+        };
+        if definitions[key.as_str()] != idx {
+            continue;
+        }
+
+        if existing.contains(key) {
+            if let Some(definition) = find_definition(lines, key, policy)? {
+                cursor = definition + 1;
+            }
+            continue;
+        }
+
+        let start = comment_block_start(template, idx);
+        let mut entry: Vec<EnvLine> = template[start..=idx].to_vec();
+        let blank_before = start > 0 && matches!(template[start - 1], EnvLine::Empty(_));
+        let blank_after = matches!(template.get(idx + 1), Some(EnvLine::Empty(_)));
+        if blank_before && cursor > 0 && !matches!(lines[cursor - 1], EnvLine::Empty(_)) {
+            entry.insert(0, EnvLine::Empty(String::new()));
+        }
+        // Later keys go between this entry and its trailing blank line
+        let next = cursor + entry.len();
+        if blank_after && !matches!(lines.get(cursor), None | Some(EnvLine::Empty(_))) {
+            entry.push(EnvLine::Empty(String::new()));
+        }
+        for line in &mut entry {
+            set_line_ending(line, crlf);
+        }
+
+        lines.splice(cursor..cursor, entry);
+        cursor = next;
+        summary.added.push(key.clone());
+    }
+
+    Ok(summary)
+}
+
+/// Index of the first line in the run of comments directly above `lines[idx]`
+fn comment_block_start(lines: &[EnvLine], idx: usize) -> usize {
+    let mut start = idx;
+    while start > 0 && matches!(lines[start - 1], EnvLine::Comment(_)) {
+        start -= 1;
+    }
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_env_file, write_env_file};
+    use pretty_assertions::assert_eq;
+
+    const TEMPLATE: &str = "\
+# Example configuration
+
+# Database
+DB_HOST=localhost
+DB_PORT=5432
+
+# Redis
+REDIS_URL=redis://localhost:6379
+";
+
+    fn sync(content: &str, template: &str) -> (String, SyncSummary) {
+        let mut lines = parse_env_file(content);
+        let summary = sync_env_vars(
+            &mut lines,
+            &parse_env_file(template),
+            DuplicatePolicy::LastWins,
+        )
+        .unwrap();
+        (write_env_file(&lines), summary)
+    }
+
+    #[test]
+    fn test_sync_into_empty_file_copies_template() {
+        let (content, summary) = sync("", TEMPLATE);
+        assert_eq!(
+            content,
+            "# Database\nDB_HOST=localhost\nDB_PORT=5432\n\n# Redis\nREDIS_URL=redis://localhost:6379\n"
+        );
+        assert_eq!(summary.added, vec!["DB_HOST", "DB_PORT", "REDIS_URL"]);
+        assert!(summary.not_in_template.is_empty());
+    }
+
+    #[test]
+    fn test_sync_inserts_missing_keys_in_template_order() {
+        let (content, summary) = sync(
+            "# mine\nDB_HOST=db.internal\nSECRET=x\nREDIS_URL=redis://cache\n",
+            TEMPLATE,
+        );
+        assert_eq!(
+            content,
+            "# mine\nDB_HOST=db.internal\nDB_PORT=5432\n\nSECRET=x\nREDIS_URL=redis://cache\n"
+        );
+        assert_eq!(summary.added, vec!["DB_PORT"]);
+        assert_eq!(summary.not_in_template, vec!["SECRET"]);
+    }
+
+    #[test]
+    fn test_sync_mirrors_blank_lines_and_comments() {
+        let (content, _) = sync("DB_HOST=db\nDB_PORT=1\nOTHER=2\n", TEMPLATE);
+        assert_eq!(
+            content,
+            "DB_HOST=db\nDB_PORT=1\n\n# Redis\nREDIS_URL=redis://localhost:6379\nOTHER=2\n"
+        );
+    }
+
+    #[test]
+    fn test_sync_places_leading_keys_above_first_known_key() {
+        let (content, summary) = sync("# header\n\n# port\nDB_PORT=1\n", TEMPLATE);
+        assert_eq!(
+            content,
+            "# header\n\n# Database\nDB_HOST=localhost\n# port\nDB_PORT=1\n\n# Redis\nREDIS_URL=redis://localhost:6379\n"
+        );
+        assert_eq!(summary.added, vec!["DB_HOST", "REDIS_URL"]);
+    }
+
+    #[test]
+    fn test_sync_copies_lines_with_the_file_line_ending() {
+        let crlf_template = TEMPLATE.replace('\n', "\r\n");
+        let (content, _) = sync("DB_HOST=db\nOTHER=2\n", &crlf_template);
+        assert_eq!(
+            content,
+            "DB_HOST=db\nDB_PORT=5432\n\n# Redis\nREDIS_URL=redis://localhost:6379\n\nOTHER=2\n"
+        );
+
+        let (content, _) = sync("DB_HOST=db\r\nOTHER=2\r\n", TEMPLATE);
+        assert_eq!(
+            content,
+            "DB_HOST=db\r\nDB_PORT=5432\r\n\r\n# Redis\r\nREDIS_URL=redis://localhost:6379\r\n\r\nOTHER=2\r\n"
+        );
+
+        let (content, _) = sync("", &crlf_template);
+        assert_eq!(
+            content.matches("\r\n").count(),
+            content.matches('\n').count()
+        );
+    }
+
+    #[test]
+    fn test_sync_never_overwrites_and_is_idempotent() {
+        let original = "DB_PORT=9999\n";
+        let (content, _) = sync(original, TEMPLATE);
+        assert!(content.contains("DB_PORT=9999"));
+        assert!(!content.contains("5432"));
+
+        let (again, summary) = sync(&content, TEMPLATE);
+        assert_eq!(again, content);
+        assert!(summary.added.is_empty());
+    }
+
+    #[test]
+    fn test_sync_keeps_template_formatting() {
+        let (content, _) = sync("", "export GREETING='hello world' # shown to users\n");
+        assert_eq!(content, "export GREETING='hello world' # shown to users\n");
+    }
+
+    #[test]
+    fn test_sync_duplicate_policy() {
+        let template = "A=first\nA=second\n";
+        let mut lines = Vec::new();
+        sync_env_vars(
+            &mut lines,
+            &parse_env_file(template),
+            DuplicatePolicy::FirstWins,
+        )
+        .unwrap();
+        assert_eq!(write_env_file(&lines), "A=first\n");
+
+        assert!(sync_env_vars(
+            &mut Vec::new(),
+            &parse_env_file(template),
+            DuplicatePolicy::Error
+        )
+        .is_err());
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.
@@ -0,0 +1,1378 @@
+// Integration tests for the file-editing and inspection subcommands
+// These tests run the binary against temporary .env files and check both output and file contents
+
+use assert_cmd::Command;
+use durable_appconfig_dotenv::lock_env_file;
+use std::fs;
+use std::process::Command as StdCommand;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_keys_reports_bare_key_for_export_prefix() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "export FOO=bar\nBAR=baz\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("keys")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "FOO\nBAR\n");
+}
+
+#[test]
+fn test_check_passes_clean_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "# comment\nFOO=bar\nexport BAR='baz'\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("check")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+}
+
+#[test]
+fn test_check_reports_problems_and_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "FOO=bar\n1BAD=x\nno equals here\nQ=\"open\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("check")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let path = env_file.display();
+    assert!(stdout.contains(&format!("{path}:2:1: error: invalid key '1BAD'")));
+    assert!(stdout.contains(&format!("{path}:3:1: error: expected KEY=value")));
+    assert!(stdout.contains(&format!("{path}:4:3: error: unterminated quoted value")));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Found 3 error(s)"));
+}
+
+#[test]
+fn test_check_missing_file_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .arg("check")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_check_warns_about_duplicate_keys() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "HOST=a\nPORT=1\nHOST=b\n").unwrap();
+    let path = env_file.display();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("check")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{path}:3:1: warning: duplicate key 'HOST' (first defined on line 1)\n")
+    );
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["--duplicates", "error", "check"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("3:1: error: duplicate key 'HOST'"));
+}
+
+#[test]
+fn test_duplicate_policy_is_consistent_between_set_and_get() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "HOST=a\nHOST=b\n").unwrap();
+
+    for (policy, expected) in [
+        ("last-wins", "HOST=a\nHOST=c\n"),
+        ("first-wins", "HOST=d\nHOST=c\n"),
+    ] {
+        let value = if policy == "last-wins" { "c" } else { "d" };
+        let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+        cmd.arg("--file")
+            .arg(&env_file)
+            .args(["--duplicates", policy, "set", &format!("HOST={value}")])
+            .assert()
+            .success();
+        assert_eq!(fs::read_to_string(&env_file).unwrap(), expected);
+
+        let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+        let output = cmd
+            .arg("--file")
+            .arg(&env_file)
+            .args(["--duplicates", policy, "get", "HOST"])
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            format!("{value}\n")
+        );
+    }
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["--duplicates", "error", "export"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Duplicate key: HOST"));
+}
+
+#[test]
+fn test_dedupe_collapses_duplicates() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(
+        &env_file,
+        "# primary\nHOST=a\nPORT=1\n# fallback\nHOST=b\nPORT=2\nPORT=3\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("dedupe")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "HOST: removed 1 duplicate definition(s)\nPORT: removed 2 duplicate definition(s)\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "# primary\n# fallback\nHOST=b\nPORT=3\n"
+    );
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("dedupe")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No duplicate keys"));
+}
+
+#[test]
+fn test_sync_default_template_is_next_to_the_env_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let sub = temp_dir.path().join("sub");
+    fs::create_dir(&sub).unwrap();
+
+    fs::write(temp_dir.path().join(".env.example"), "WRONG=1\n").unwrap();
+    fs::write(sub.join(".env.example"), "RIGHT=1\n").unwrap();
+    fs::write(sub.join(".env"), "").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["--file", "sub/.env", "sync"])
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(sub.join(".env")).unwrap(), "RIGHT=1\n");
+}
+
+#[test]
+fn test_sync_adds_missing_keys_from_template() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let template = temp_dir.path().join(".env.example");
+
+    fs::write(
+        &template,
+        "# Database\nDB_HOST=localhost\nDB_PORT=5432\n\n# Redis\nREDIS_URL=redis://localhost\n",
+    )
+    .unwrap();
+    fs::write(&env_file, "DB_HOST=db.internal\nLOCAL_ONLY=1\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("sync")
+        .arg("--template")
+        .arg(&template)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "added: DB_PORT, REDIS_URL\nnot in template: LOCAL_ONLY\n2 added, 1 not in template\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "DB_HOST=db.internal\nDB_PORT=5432\n\n# Redis\nREDIS_URL=redis://localhost\n\nLOCAL_ONLY=1\n"
+    );
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("sync")
+        .arg("--template")
+        .arg(temp_dir.path().join("missing.example"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to read template"));
+}
+
+#[test]
+fn test_example_replaces_values_with_placeholders() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let example_file = temp_dir.path().join(".env.example");
+
+    fs::write(
+        &env_file,
+        "# Server\n# @public\nHOST=0.0.0.0\nPORT=8080\n\nDB_PASSWORD='hunter2' # required\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("example")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# Server\n# @public\nHOST=0.0.0.0\nPORT=\n\nDB_PASSWORD='' # required\n"
+    );
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("example")
+        .arg("--placeholder")
+        .arg("type")
+        .arg("--output")
+        .arg(&example_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        fs::read_to_string(&example_file).unwrap(),
+        "# Server\n# @public\nHOST=0.0.0.0\nPORT=\"<int>\"\n\nDB_PASSWORD='' # required\n"
+    );
+}
+
+#[test]
+fn test_diff_reports_changes_and_masks_values() {
+    let temp_dir = TempDir::new().unwrap();
+    let a = temp_dir.path().join(".env.a");
+    let b = temp_dir.path().join(".env.b");
+
+    fs::write(&a, "# shared\nHOST=localhost\nPASSWORD=old\nLEGACY=1\n").unwrap();
+    fs::write(&b, "HOST=localhost\nPASSWORD='new value'\nPORT=8080\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd.arg("diff").arg(&a).arg(&b).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "~ PASSWORD: ******** -> ********\n- LEGACY=********\n+ PORT=********\n"
+    );
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("diff")
+        .arg(&a)
+        .arg(&b)
+        .arg("--show-values")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "~ PASSWORD: old -> \"new value\"\n- LEGACY=1\n+ PORT=8080\n"
+    );
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("diff")
+        .arg(&a)
+        .arg(&b)
+        .arg("--keys-only")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "~ PASSWORD\n- LEGACY\n+ PORT\n"
+    );
+}
+
+#[test]
+fn test_diff_identical_files_succeed() {
+    let temp_dir = TempDir::new().unwrap();
+    let a = temp_dir.path().join(".env.a");
+    let b = temp_dir.path().join(".env.b");
+
+    fs::write(&a, "# comment\nHOST=localhost\nPORT=8080\n").unwrap();
+    fs::write(&b, "export PORT=\"8080\"\nHOST=localhost # inline\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd.arg("diff").arg(&a).arg(&b).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_diff_errors_exit_with_status_2() {
+    let temp_dir = TempDir::new().unwrap();
+    let a = temp_dir.path().join(".env.a");
+    let b = temp_dir.path().join(".env.b");
+
+    fs::write(&a, "HOST=localhost\n").unwrap();
+    fs::write(&b, "HOST=localhost\nHOST=db\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("diff")
+        .arg(&a)
+        .arg(temp_dir.path().join("missing.env"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("File not found"));
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .args(["--duplicates", "error", "diff"])
+        .arg(&a)
+        .arg(&b)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Duplicate key: HOST"));
+}
+
+#[test]
+fn test_validate_against_schema() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let schema = temp_dir.path().join(".env.schema");
+
+    fs::write(
+        &schema,
+        "[PORT]\ntype = \"int\"\nrequired = true\n\n[SECRET_KEY]\nrequired = true\nsecret = true\npattern = \"[a-f0-9]{8}\"\n\n[LOG_LEVEL]\ntype = \"enum\"\nvalues = [\"info\", \"debug\"]\n",
+    )
+    .unwrap();
+    fs::write(&env_file, "PORT=80a\nSECRET_KEY=hunter2\nLOG_LEVEL=info\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("validate")
+        .arg("--schema")
+        .arg(&schema)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(":1:1: error: 'PORT' must be an integer (got \"80a\")"));
+    assert!(stdout.contains(":2:1: error: 'SECRET_KEY' does not match the pattern [a-f0-9]{8}\n"));
+    assert!(!stdout.contains("hunter2"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Found 2 violation(s)"));
+
+    fs::write(&env_file, "PORT=8080\nLOG_LEVEL=debug\n").unwrap();
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("validate")
+        .arg("--schema")
+        .arg(&schema)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .ends_with(".env: error: missing required key 'SECRET_KEY'\n"));
+
+    fs::write(&env_file, "PORT=8080\nSECRET_KEY=deadbeef\n").unwrap();
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .arg("validate")
+        .arg("--schema")
+        .arg(&schema)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_validate_default_schema_is_next_to_the_env_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let sub = temp_dir.path().join("sub");
+    fs::create_dir(&sub).unwrap();
+
+    fs::write(
+        temp_dir.path().join(".env.schema"),
+        "[OTHER]\nrequired = true\n",
+    )
+    .unwrap();
+    fs::write(sub.join(".env.schema"), "[PORT]\ntype = \"int\"\n").unwrap();
+    fs::write(sub.join(".env"), "PORT=abc\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .current_dir(temp_dir.path())
+        .args(["--file", "sub/.env", "validate"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "sub/.env:1:1: error: 'PORT' must be an integer (got \"abc\")\n"
+    );
+}
+
+#[test]
+fn test_validate_with_inline_annotations() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(
+        &env_file,
+        "# @type int\n# @required\nPORT=http\n# @enum debug|info\nLOG_LEVEL=info\n# @secret\n# @pattern sk_.*\nAPI_KEY=hunter2\n# @typo\nOTHER=1\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .current_dir(temp_dir.path())
+        .arg("validate")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        ".env:3:1: error: 'PORT' must be an integer (got \"http\")\n\
+         .env:8:1: error: 'API_KEY' does not match the pattern sk_.*\n\
+         .env:9:3: warning: unknown annotation '@typo' is ignored\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Found 2 violation(s)"));
+
+    fs::write(&env_file, "# @todo rotate\n# @type int\nPORT=8080\n").unwrap();
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .current_dir(temp_dir.path())
+        .arg("validate")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        ".env:1:3: warning: unknown annotation '@todo' is ignored\n"
+    );
+
+    fs::write(&env_file, "PORT=1\n").unwrap();
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .current_dir(temp_dir.path())
+        .arg("validate")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Nothing to validate against"));
+}
+
+#[test]
+fn test_encrypt_and_transparent_decrypt() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env.production");
+    let key_file = temp_dir.path().join(".env.production.key");
+
+    fs::write(
+        &env_file,
+        "HOST=db.internal\n# @secret\nPASSWORD=hunter2\nTOKEN=abc\n",
+    )
+    .unwrap();
+
+    let dotenv = || {
+        let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+        cmd.env_remove("DOTENV_KEY").arg("--file").arg(&env_file);
+        cmd
+    };
+
+    // Without keys, encrypt takes the keys annotated @secret and creates the key file
+    let output = dotenv().arg("encrypt").output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Created key file"));
+    assert!(stdout.contains("encrypted: PASSWORD"));
+    assert!(key_file.exists());
+
+    let content = fs::read_to_string(&env_file).unwrap();
+    assert!(content.starts_with("HOST=db.internal\n# @secret\nPASSWORD=enc:v1:"));
+    assert!(content.ends_with("\nTOKEN=abc\n"));
+    assert!(!content.contains("hunter2"));
+
+    let output = dotenv()
+        .args(["encrypt", "PASSWORD", "TOKEN"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "encrypted: TOKEN\nalready encrypted: PASSWORD\n"
+    );
+
+    // get, export and run see plaintext
+    let output = dotenv().args(["get", "PASSWORD"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hunter2\n");
+    let output = dotenv()
+        .args(["export", "--format", "json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["TOKEN"], "abc");
+    let output = dotenv()
+        .args(["run", "--", "sh", "-c", "echo $PASSWORD $TOKEN"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hunter2 abc\n");
+
+    // DOTENV_KEY works without the key file; without any key values pass through
+    let key = fs::read_to_string(&key_file).unwrap();
+    fs::remove_file(&key_file).unwrap();
+    let output = dotenv()
+        .env("DOTENV_KEY", key.trim())
+        .args(["get", "TOKEN"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "abc\n");
+    let output = dotenv().args(["get", "TOKEN"]).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("enc:v1:"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no key found"));
+
+    let output = dotenv().arg("decrypt").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No key found"));
+
+    let output = dotenv()
+        .env("DOTENV_KEY", key.trim())
+        .arg("decrypt")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "decrypted: PASSWORD, TOKEN\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "HOST=db.internal\n# @secret\nPASSWORD=hunter2\nTOKEN=abc\n"
+    );
+}
+
+#[test]
+fn test_set_and_randomize_keep_encrypted_values_encrypted() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let key_file = temp_dir.path().join(".env.key");
+
+    fs::write(&env_file, "HOST=db\n# @secret\nPIN=1234\n").unwrap();
+
+    let dotenv = || {
+        let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+        cmd.env_remove("DOTENV_KEY").arg("--file").arg(&env_file);
+        cmd
+    };
+
+    dotenv().arg("encrypt").assert().success();
+    dotenv()
+        .args(["set", "PIN=5678", "HOST=db2"])
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&env_file).unwrap();
+    assert!(content.starts_with("HOST=db2\n# @secret\nPIN=enc:v1:"));
+    let output = dotenv().args(["get", "PIN"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "5678\n");
+
+    dotenv().args(["randomize", "PIN"]).assert().success();
+    let content = fs::read_to_string(&env_file).unwrap();
+    assert!(content.contains("PIN=enc:v1:"));
+    let output = dotenv().args(["get", "PIN"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim().len(), 32);
+
+    // Without the key the write is refused and the file left untouched
+    fs::remove_file(&key_file).unwrap();
+    let output = dotenv().args(["set", "PIN=0000"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Refusing to write a plaintext value"));
+    assert_eq!(fs::read_to_string(&env_file).unwrap(), content);
+
+    // Keys that are not encrypted can still be set without a key
+    dotenv().args(["set", "HOST=db3"]).assert().success();
+}
+
+#[test]
+fn test_validate_encrypted_values() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let key_file = temp_dir.path().join(".env.key");
+
+    fs::write(&env_file, "# @type int\n# @secret\nPIN=1234\n").unwrap();
+
+    let dotenv = || {
+        let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+        cmd.env_remove("DOTENV_KEY")
+            .current_dir(temp_dir.path())
+            .arg("--file")
+            .arg(&env_file);
+        cmd
+    };
+
+    dotenv().arg("encrypt").assert().success();
+
+    // With the key the decrypted value is type-checked
+    dotenv().arg("validate").assert().success();
+
+    // Without it only presence is checked
+    let key = fs::read_to_string(&key_file).unwrap();
+    fs::remove_file(&key_file).unwrap();
+    dotenv().arg("validate").assert().success();
+
+    let mut lines: Vec<String> = fs::read_to_string(&env_file)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    lines.push("# @required\nTOKEN=".to_string());
+    fs::write(&env_file, lines.join("\n") + "\n").unwrap();
+    let output = dotenv().arg("validate").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("required key 'TOKEN' is empty"));
+
+    // A decrypted value that breaks the schema is still reported
+    fs::write(&key_file, key).unwrap();
+    dotenv()
+        .args(["set", "PIN=abc", "TOKEN=t"])
+        .assert()
+        .success();
+    let output = dotenv().arg("validate").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("'PIN' must be an integer"));
+}
+
+#[test]
+fn test_failed_encrypt_does_not_create_key_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let key_file = temp_dir.path().join(".env.key");
+
+    fs::write(&env_file, "HOST=db\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .env_remove("DOTENV_KEY")
+        .arg("--file")
+        .arg(&env_file)
+        .args(["encrypt", "HOST", "MISSING"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Key not found: MISSING"));
+    assert!(!key_file.exists());
+    assert_eq!(fs::read_to_string(&env_file).unwrap(), "HOST=db\n");
+}
+
+#[test]
+fn test_decrypt_with_wrong_key_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let other_key = temp_dir.path().join("other.key");
+
+    fs::write(&env_file, "SECRET=value\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.env_remove("DOTENV_KEY")
+        .arg("--file")
+        .arg(&env_file)
+        .args(["encrypt", "SECRET"])
+        .assert()
+        .success();
+
+    // An explicit key file that does not exist is created by encrypt
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.env_remove("DOTENV_KEY")
+        .arg("--file")
+        .arg(temp_dir.path().join(".env.other"))
+        .arg("--key-file")
+        .arg(&other_key)
+        .args(["set", "X=1"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.env_remove("DOTENV_KEY")
+        .arg("--file")
+        .arg(temp_dir.path().join(".env.other"))
+        .arg("--key-file")
+        .arg(&other_key)
+        .args(["encrypt", "X"])
+        .assert()
+        .success();
+    assert!(other_key.exists());
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .env_remove("DOTENV_KEY")
+        .arg("--file")
+        .arg(&env_file)
+        .arg("--key-file")
+        .arg(&other_key)
+        .args(["get", "SECRET"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("wrong key or corrupted value"));
+}
+
+#[test]
+fn test_import_json_and_yaml_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let json_file = temp_dir.path().join("config.json");
+    let yaml_file = temp_dir.path().join("config.yml");
+
+    fs::write(&env_file, "# app\nHOST=localhost\n").unwrap();
+    fs::write(&json_file, r#"{"HOST": "localhost", "PORT": 8080}"#).unwrap();
+    fs::write(&yaml_file, "DEBUG: true\nGREETING: hello world\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("import")
+        .arg(&json_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "added: PORT\nunchanged: HOST\n1 added, 0 changed, 1 unchanged, 0 kept existing\n"
+    );
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .arg("import")
+        .arg(&yaml_file)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "# app\nHOST=localhost\nPORT=8080\nDEBUG=true\nGREETING=\"hello world\"\n"
+    );
+}
+
+#[test]
+fn test_import_conflicts_need_a_choice() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "HOST=old\n").unwrap();
+
+    let import = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+        cmd.arg("--file")
+            .arg(&env_file)
+            .arg("import")
+            .args(args)
+            .write_stdin("HOST=new\nEXTRA=1\n")
+            .output()
+            .unwrap()
+    };
+
+    let output = import(&["-"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("use --overwrite or --keep-existing"));
+    assert_eq!(fs::read_to_string(&env_file).unwrap(), "HOST=old\n");
+
+    let output = import(&["-", "--keep-existing"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("kept existing: HOST\n"));
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "HOST=old\nEXTRA=1\n"
+    );
+
+    let output = import(&["-", "--overwrite"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("changed: HOST\n"));
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "HOST=new\nEXTRA=1\n"
+    );
+}
+
+#[test]
+fn test_import_keeps_encrypted_values_encrypted() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let key_file = temp_dir.path().join(".env.key");
+
+    fs::write(&env_file, "HOST=db\n# @secret\nPIN=1234\n").unwrap();
+
+    let dotenv = || {
+        let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+        cmd.env_remove("DOTENV_KEY").arg("--file").arg(&env_file);
+        cmd
+    };
+
+    dotenv().arg("encrypt").assert().success();
+
+    // The same plaintext counts as unchanged and leaves the ciphertext alone
+    let content = fs::read_to_string(&env_file).unwrap();
+    let output = dotenv()
+        .args(["import", "-"])
+        .write_stdin("PIN=1234\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("unchanged: PIN\n"));
+    assert_eq!(fs::read_to_string(&env_file).unwrap(), content);
+
+    dotenv()
+        .args(["import", "-", "--overwrite"])
+        .write_stdin("PIN=5678\n")
+        .assert()
+        .success();
+    let content = fs::read_to_string(&env_file).unwrap();
+    assert!(content.starts_with("HOST=db\n# @secret\nPIN=enc:v1:"));
+    let output = dotenv().args(["get", "PIN"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "5678\n");
+
+    // Without the key the write is refused and the file left untouched
+    fs::remove_file(&key_file).unwrap();
+    let output = dotenv()
+        .args(["import", "-", "--overwrite"])
+        .write_stdin("PIN=0000\n")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Refusing to write a plaintext value"));
+    assert_eq!(fs::read_to_string(&env_file).unwrap(), content);
+}
+
+#[test]
+fn test_import_docker_env_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let docker_file = temp_dir.path().join("app.env");
+
+    fs::write(&docker_file, "# docker\nMESSAGE=say \"hi\" # literally\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .arg("import")
+        .arg(&docker_file)
+        .args(["--format", "docker"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["get", "MESSAGE"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "say \"hi\" # literally\n"
+    );
+}
+
+#[test]
+fn test_import_process_env_with_prefix() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .args([
+            "import",
+            "--process-env",
+            "--prefix",
+            "MYAPP_",
+            "--strip-prefix",
+        ])
+        .env("MYAPP_PORT", "8080")
+        .env("MYAPP_NAME", "demo app")
+        .env("OTHER_VAR", "ignored")
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "NAME=\"demo app\"\nPORT=8080\n"
+    );
+}
+
+#[test]
+fn test_import_flattens_nested_json() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let json_file = temp_dir.path().join("config.json");
+
+    fs::write(
+        &json_file,
+        r#"{"db": {"host": "localhost", "replicas": ["r1", "r2"]}, "debug": false}"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .arg("import")
+        .arg(&json_file)
+        .arg("--uppercase")
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "DB__HOST=localhost\nDB__REPLICAS__0=r1\nDB__REPLICAS__1=r2\nDEBUG=false\n"
+    );
+}
+
+#[test]
+fn test_run_passes_variables_to_command() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "GREETING=\"hello world\"\nexport NAME=env\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["run", "--", "sh", "-c", "echo \"$GREETING from $NAME\""])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "hello world from env\n"
+    );
+}
+
+#[test]
+fn test_run_override_and_keep_existing() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "MODE=file\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .env("MODE", "process")
+        .arg("--file")
+        .arg(&env_file)
+        .args(["run", "--", "sh", "-c", "echo $MODE"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "file\n");
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .env("MODE", "process")
+        .arg("--file")
+        .arg(&env_file)
+        .args(["run", "--no-override", "--", "sh", "-c", "echo $MODE"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "process\n");
+}
+
+#[test]
+fn test_run_clean_environment() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "ONLY=this\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .env("LEAKED", "1")
+        .arg("--file")
+        .arg(&env_file)
+        .args(["run", "--clean", "--", "env"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ONLY=this"));
+    assert!(!stdout.contains("LEAKED"));
+}
+
+#[test]
+fn test_run_extra_files_take_precedence() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    let local_file = temp_dir.path().join(".env.local");
+
+    fs::write(&env_file, "A=base\nB=base\n").unwrap();
+    fs::write(&local_file, "B=local\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("run")
+        .arg("--extra")
+        .arg(&local_file)
+        .args(["--", "sh", "-c", "echo $A $B"])
+        .output()
+        .unwrap();
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "base local\n");
+}
+
+#[test]
+fn test_run_propagates_exit_code() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .args(["run", "--", "sh", "-c", "exit 7"])
+        .assert()
+        .code(7);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_propagates_signals() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["run", "--", "sh", "-c", "kill -TERM $$"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.signal(), Some(15));
+}
+
+#[test]
+fn test_run_missing_program_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["run", "--", "definitely-not-a-real-program-xyz"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to execute command"));
+}
+
+#[test]
+fn test_get_prints_decoded_values() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "A=\"hello world\"\nB='$literal'\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["get", "A", "B"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "hello world\n$literal\n"
+    );
+}
+
+#[test]
+fn test_get_shell_and_json_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "A=\"say \\\"hi\\\" $USER\"\nB=plain\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["get", "--shell", "A", "B"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "\"say \\\"hi\\\" \\$USER\"\nplain\n"
+    );
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["get", "--json", "A", "B"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["A"], "say \"hi\" $USER");
+    assert_eq!(json["B"], "plain");
+}
+
+#[test]
+fn test_get_missing_key_exit_code() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "A=1\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["get", "A", "MISSING"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("MISSING"));
+}
+
+#[test]
+fn test_get_default_for_missing_key() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "A=1\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["get", "--default", "fallback", "A", "MISSING"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\nfallback\n");
+}
+
+#[test]
+fn test_set_value_with_hash_survives_reload() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .arg("set")
+        .arg("MOTTO=a # b")
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .arg("export")
+        .arg("--format")
+        .arg("json")
+        .output()
+        .unwrap();
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["MOTTO"], "a # b");
+}
+
+#[test]
+fn test_set_preserves_untouched_lines() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(
+        &env_file,
+        "# Database\nexport DB_HOST = localhost  # dev only\nDB_PASS='s3cret'\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .arg("set")
+        .arg("DB_HOST=db.internal")
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "# Database\nexport DB_HOST = db.internal  # dev only\nDB_PASS='s3cret'\n"
+    );
+}
+
+#[test]
+fn test_set_preserves_crlf_line_endings() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    fs::write(&env_file, "# Database\r\nA=1\r\nB='two'  # note\r\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.arg("--file")
+        .arg(&env_file)
+        .arg("set")
+        .arg("C=4")
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "# Database\r\nA=1\r\nB='two'  # note\r\nC=4\r\n"
+    );
+}
+
+#[test]
+fn test_set_with_bare_relative_filename() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .arg("--file")
+        .arg("local.env")
+        .arg("set")
+        .arg("KEY=value")
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("local.env")).unwrap(),
+        "KEY=value\n"
+    );
+}
+
+#[test]
+fn test_set_fails_when_file_is_locked() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    let _lock = lock_env_file(&env_file, Duration::ZERO).unwrap();
+
+    let mut cmd = Command::cargo_bin("durable-appconfig-dotenv").unwrap();
+    let output = cmd
+        .arg("--file")
+        .arg(&env_file)
+        .args(["set", "--lock-timeout", "0", "A=1"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("another process is editing"));
+    assert!(!env_file.exists());
+}
+
+#[test]
+fn test_concurrent_sets_do_not_lose_updates() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+
+    let children: Vec<_> = (0..8)
+        .map(|i| {
+            StdCommand::new(assert_cmd::cargo::cargo_bin("durable-appconfig-dotenv"))
+                .arg("--file")
+                .arg(&env_file)
+                .arg("set")
+                .arg(format!("KEY_{i}=value_{i}"))
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    let content = fs::read_to_string(&env_file).unwrap();
+    for i in 0..8 {
+        assert!(
+            content.contains(&format!("KEY_{i}=value_{i}\n")),
+            "missing KEY_{i} in:\n{content}"
+        );
+    }
+}
+
+// Copyright (c) 2025 Durable Programming, LLC. All rights reserved.